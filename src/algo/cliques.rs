@@ -0,0 +1,260 @@
+use crate::algo::coloring::degeneracy_order;
+use crate::algo::structure::{simple_adjacency, SimpleAdjacency};
+use crate::graph::traits::{GraphIter, UndirectedGraph};
+
+use std::cmp::Ordering;
+
+// Bron-Kerbosch with Tomita pivoting. The outer level walks the nodes in degeneracy order so each
+// top-level candidate set only holds the later neighbors of a node, which keeps sparse graphs cheap
+pub fn maximal_cliques<G>(graph: &G) -> MaximalCliques<G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    MaximalCliques::new(graph)
+}
+
+// Largest clique in the graph, empty if the graph has no nodes
+pub fn max_clique<G>(graph: &G) -> Vec<G::NId>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    maximal_cliques(graph)
+        .max_by_key(|clique| clique.len())
+        .unwrap_or_default()
+}
+
+pub fn clique_number<G>(graph: &G) -> usize
+where
+    G: UndirectedGraph + GraphIter,
+{
+    max_clique(graph).len()
+}
+
+pub struct MaximalCliques<G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    ids: Vec<G::NId>,
+    adj: Vec<Vec<usize>>,
+    order: Vec<usize>,
+    position: Vec<usize>,
+    next_root: usize,
+    stack: Vec<Frame>,
+}
+
+// One level of the Bron-Kerbosch recursion: the clique so far, the nodes that may still extend it,
+// the nodes that were already tried, and the candidates from p that remain to branch on
+struct Frame {
+    r: Vec<usize>,
+    p: Vec<usize>,
+    x: Vec<usize>,
+    candidates: Vec<usize>,
+}
+
+impl<G> Iterator for MaximalCliques<G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    type Item = Vec<G::NId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = match self.stack.last_mut() {
+                Some(frame) => frame,
+                None => {
+                    let v = *self.order.get(self.next_root)?;
+                    self.next_root += 1;
+
+                    let (mut p, mut x) = (Vec::new(), Vec::new());
+                    for &w in &self.adj[v] {
+                        if self.position[w] > self.position[v] {
+                            p.push(w);
+                        } else {
+                            x.push(w);
+                        }
+                    }
+                    if p.is_empty() && x.is_empty() {
+                        return Some(vec![self.ids[v]]);
+                    }
+                    if !p.is_empty() {
+                        let frame = Frame::new(vec![v], p, x, &self.adj);
+                        self.stack.push(frame);
+                    }
+                    continue;
+                }
+            };
+
+            let v = match frame.candidates.pop() {
+                Some(v) => v,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            let p = intersect(&frame.p, &self.adj[v]);
+            let x = intersect(&frame.x, &self.adj[v]);
+            let mut r = frame.r.clone();
+            r.push(v);
+
+            if let Ok(i) = frame.p.binary_search(&v) {
+                frame.p.remove(i);
+            }
+            if let Err(i) = frame.x.binary_search(&v) {
+                frame.x.insert(i, v);
+            }
+
+            if p.is_empty() && x.is_empty() {
+                return Some(r.into_iter().map(|i| self.ids[i]).collect());
+            }
+            if !p.is_empty() {
+                let frame = Frame::new(r, p, x, &self.adj);
+                self.stack.push(frame);
+            }
+        }
+    }
+}
+
+impl<G> MaximalCliques<G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    fn new(graph: &G) -> Self {
+        let SimpleAdjacency { ids, index, adj } = simple_adjacency(graph);
+
+        let order: Vec<usize> = degeneracy_order(graph)
+            .into_iter()
+            .map(|id| index[&id])
+            .collect();
+        let mut position = vec![0; ids.len()];
+        for (k, &v) in order.iter().enumerate() {
+            position[v] = k;
+        }
+
+        MaximalCliques {
+            ids,
+            adj,
+            order,
+            position,
+            next_root: 0,
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl Frame {
+    // branches only on p \ N(pivot), where the pivot from p ∪ x has the most neighbors in p
+    fn new(r: Vec<usize>, p: Vec<usize>, x: Vec<usize>, adj: &[Vec<usize>]) -> Frame {
+        let pivot = p
+            .iter()
+            .chain(x.iter())
+            .max_by_key(|&&u| intersect(&p, &adj[u]).len())
+            .copied()
+            .unwrap();
+        let candidates = p
+            .iter()
+            .copied()
+            .filter(|v| adj[pivot].binary_search(v).is_err())
+            .collect();
+        Frame {
+            r,
+            p,
+            x,
+            candidates,
+        }
+    }
+}
+
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                result.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::cliques::{clique_number, max_clique, maximal_cliques};
+    use crate::graph::traits::{GraphMut, KeyedGraph, OrdinalGraph};
+    use crate::graph::types::{UnListGraph, UnMapGraph};
+    use itertools::Itertools;
+
+    #[test]
+    fn two_triangles_and_a_tail() {
+        // 0 --- 1 --- 3 --- 5
+        //  \   / \   /
+        //   \ /   \ /
+        //    2     4      6
+        let graph = UnListGraph::builder()
+            .with_size(7)
+            .edge(0, 1, ())
+            .edge(0, 2, ())
+            .edge(1, 2, ())
+            .edge(1, 3, ())
+            .edge(1, 4, ())
+            .edge(3, 4, ())
+            .edge(3, 5, ())
+            .build();
+
+        let cliques = maximal_cliques(&graph)
+            .map(|clique| clique.into_iter().sorted().collect_vec())
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            cliques,
+            vec![vec![0, 1, 2], vec![1, 3, 4], vec![3, 5], vec![6]]
+        );
+        assert_eq!(clique_number(&graph), 3);
+    }
+
+    #[test]
+    fn complete_graph_is_one_clique() {
+        let mut graph = UnMapGraph::new();
+        for name in ["a", "b", "c", "d", "e"] {
+            graph.put_node(name, ());
+        }
+        for (u, v) in ["a", "b", "c", "d", "e"].into_iter().tuple_combinations() {
+            graph.insert_edge(u, v, ()).expect("nodes should exist");
+        }
+
+        let cliques = maximal_cliques(&graph).collect_vec();
+        assert_eq!(cliques.len(), 1);
+        assert_eq!(
+            max_clique(&graph).into_iter().sorted().collect_vec(),
+            vec!["a", "b", "c", "d", "e"]
+        );
+    }
+
+    #[test]
+    fn octahedron_has_eight_triangles() {
+        // K(2,2,2): every node is adjacent to all but its opposite
+        let mut graph = UnListGraph::builder().with_size(6).build();
+        for (u, v) in (0..6).tuple_combinations() {
+            if u / 2 != v / 2 {
+                graph.insert_edge(u, v, ()).expect("nodes should exist");
+            }
+        }
+
+        let cliques = maximal_cliques(&graph).collect_vec();
+        assert_eq!(cliques.len(), 8);
+        assert!(cliques.iter().all(|clique| clique.len() == 3));
+        assert_eq!(clique_number(&graph), 3);
+    }
+
+    #[test]
+    fn empty_graph() {
+        let graph = UnListGraph::<(), ()>::new();
+        assert_eq!(maximal_cliques(&graph).count(), 0);
+        assert_eq!(clique_number(&graph), 0);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::graph::traits::{GraphIter, UndirectedGraph};
+use crate::graph::types::NodeHashMap;
+use crate::iter::bfs::bfs;
+use crate::iter::traits::Path;
+
+use dary_heap::DaryHeap;
+use std::cmp::Reverse;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ColoringOrder {
+    // order in which the graph yields its nodes
+    Natural,
+    // nodes sorted by decreasing degree (Welsh-Powell)
+    LargestFirst,
+    // reverse of the order in which nodes are removed when repeatedly removing a node of minimum
+    // degree, uses at most degeneracy + 1 colors
+    SmallestLast,
+}
+
+pub enum Bipartiteness<'a, G>
+where
+    G: 'a + UndirectedGraph + GraphIter,
+{
+    // maps every node to side 0 or 1 so that no edge joins two nodes on the same side
+    Bipartite(NodeHashMap<G, usize>),
+    // cycle with an odd number of edges, starting and ending at the same node
+    OddCycle(Path<'a, G>),
+}
+
+// Greedily assigns every node the smallest color not used by its already colored neighbors,
+// visiting nodes in the given order. Returns map of node ids to colors, where colors count up from 0
+pub fn greedy_coloring<G>(graph: &G, order: ColoringOrder) -> NodeHashMap<G, usize>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let ids: Vec<G::NId> = match order {
+        ColoringOrder::Natural => graph.nodes().map(|node| node.id()).collect(),
+        ColoringOrder::LargestFirst => {
+            let mut ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+            ids.sort_by_key(|&id| Reverse(graph.degree(id)));
+            ids
+        }
+        ColoringOrder::SmallestLast => {
+            let mut ids = degeneracy_order(graph);
+            ids.reverse();
+            ids
+        }
+    };
+
+    let mut colors = NodeHashMap::<G, usize>::default();
+    for id in ids {
+        let color = smallest_free_color(graph, &colors, id);
+        colors.insert(id, color);
+    }
+    colors
+}
+
+// DSATUR: repeatedly colors the uncolored node with the most distinctly colored neighbors, breaking
+// ties by degree. Optimal for bipartite graphs, cycles and wheels
+pub fn dsatur<G>(graph: &G) -> NodeHashMap<G, usize>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+
+    let mut neighbor_colors: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    let mut heap: DaryHeap<(usize, usize, Reverse<usize>), 4> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| (0, graph.degree(id), Reverse(i)))
+        .collect();
+
+    let mut colors = NodeHashMap::<G, usize>::default();
+    while let Some((saturation, _, Reverse(i))) = heap.pop() {
+        let id = ids[i];
+        // skip stale heap entries
+        if colors.contains_key(&id) || saturation != neighbor_colors[i].len() {
+            continue;
+        }
+
+        let color = smallest_free_color(graph, &colors, id);
+        colors.insert(id, color);
+
+        for (_, neighbor) in graph.adj(id).unwrap() {
+            let j = index[&neighbor.id()];
+            if colors.contains_key(&neighbor.id()) || neighbor_colors[j].contains(&color) {
+                continue;
+            }
+            neighbor_colors[j].push(color);
+            heap.push((
+                neighbor_colors[j].len(),
+                graph.degree(neighbor.id()),
+                Reverse(j),
+            ));
+        }
+    }
+    colors
+}
+
+// Two-colors every connected component with a bfs, or returns an odd cycle proving that no
+// two-coloring exists
+pub fn is_bipartite<'a, G>(graph: &'a G) -> Bipartiteness<'a, G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let mut side = NodeHashMap::<G, usize>::default();
+    let mut depth = NodeHashMap::<G, usize>::default();
+    let mut parent = NodeHashMap::<G, Option<G::EId>>::default();
+
+    for root in graph.nodes() {
+        if side.contains_key(&root.id()) {
+            continue;
+        }
+        for (edge, node) in bfs(graph, root.id()) {
+            let id = node.id();
+            let d = edge.as_ref().map_or(0, |e| depth[&e.other(id)] + 1);
+            depth.insert(id, d);
+            side.insert(id, d % 2);
+            parent.insert(id, edge.map(|e| e.id()));
+        }
+    }
+
+    for edge in graph.edges() {
+        let (u, v) = (edge.u(), edge.v());
+        if side[&u] == side[&v] {
+            return Bipartiteness::OddCycle(odd_cycle(graph, &depth, &parent, edge.id()));
+        }
+    }
+
+    Bipartiteness::Bipartite(side)
+}
+
+// Builds the cycle closed by an edge between two nodes of the same bfs depth parity: the tree path
+// from their lowest common ancestor down to u, the edge itself, and the tree path from v back up
+fn odd_cycle<'a, G>(
+    graph: &'a G,
+    depth: &NodeHashMap<G, usize>,
+    parent: &NodeHashMap<G, Option<G::EId>>,
+    edge_id: G::EId,
+) -> Path<'a, G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let edge = graph.edge(edge_id).unwrap();
+    let up = |id: G::NId| {
+        let parent_id = parent[&id].expect("non-root node should have a parent edge");
+        (parent_id, graph.edge(parent_id).unwrap().other(id))
+    };
+
+    // climb from both endpoints until they meet, recording (edge, node) steps on each side
+    let (mut a, mut b) = (edge.u(), edge.v());
+    let mut down_to_u = Vec::new();
+    let mut up_from_v = Vec::new();
+    while depth[&a] > depth[&b] {
+        let (e, p) = up(a);
+        down_to_u.push((e, a));
+        a = p;
+    }
+    while depth[&b] > depth[&a] {
+        let (e, p) = up(b);
+        up_from_v.push((e, p));
+        b = p;
+    }
+    while a != b {
+        let (ea, pa) = up(a);
+        down_to_u.push((ea, a));
+        a = pa;
+        let (eb, pb) = up(b);
+        up_from_v.push((eb, pb));
+        b = pb;
+    }
+
+    let node = |id: G::NId| graph.node(id).unwrap();
+    let mut path = vec![(None, node(a))];
+    path.extend(
+        down_to_u
+            .into_iter()
+            .rev()
+            .map(|(e, id)| (graph.edge(e), node(id))),
+    );
+    let v = edge.v();
+    path.push((Some(edge), node(v)));
+    path.extend(
+        up_from_v
+            .into_iter()
+            .map(|(e, id)| (graph.edge(e), node(id))),
+    );
+    Path::new(path)
+}
+
+fn smallest_free_color<G>(graph: &G, colors: &NodeHashMap<G, usize>, id: G::NId) -> usize
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let mut used = vec![false; graph.degree(id) + 1];
+    for (_, neighbor) in graph.adj(id).unwrap() {
+        if neighbor.id() == id {
+            continue;
+        }
+        if let Some(&c) = colors.get(&neighbor.id()) {
+            if c < used.len() {
+                used[c] = true;
+            }
+        }
+    }
+    used.iter().position(|&u| !u).unwrap()
+}
+
+// Order in which nodes are removed when repeatedly removing a node of minimum remaining degree.
+// Every node has at most degeneracy(graph) neighbors later in the order
+pub fn degeneracy_order<G>(graph: &G) -> Vec<G::NId>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+
+    let mut degree: Vec<usize> = ids.iter().map(|&id| graph.degree(id)).collect();
+    let mut removed = vec![false; ids.len()];
+    let mut heap: DaryHeap<Reverse<(usize, usize)>, 4> = degree
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| Reverse((d, i)))
+        .collect();
+
+    let mut order = Vec::with_capacity(ids.len());
+    while let Some(Reverse((d, i))) = heap.pop() {
+        if removed[i] || d != degree[i] {
+            continue;
+        }
+        removed[i] = true;
+        order.push(ids[i]);
+        for (_, neighbor) in graph.adj(ids[i]).unwrap() {
+            let j = index[&neighbor.id()];
+            if !removed[j] {
+                degree[j] -= 1;
+                heap.push(Reverse((degree[j], j)));
+            }
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::coloring::{
+        dsatur, greedy_coloring, is_bipartite, Bipartiteness, ColoringOrder,
+    };
+    use crate::graph::traits::{GraphIter, OrdinalGraph};
+    use crate::graph::types::{NodeHashMap, UnListGraph};
+    use itertools::Itertools;
+
+    fn assert_proper(
+        graph: &UnListGraph<(), ()>,
+        colors: &NodeHashMap<UnListGraph<(), ()>, usize>,
+    ) {
+        assert_eq!(colors.len(), graph.n());
+        for edge in graph.edges() {
+            assert_ne!(colors[&edge.u()], colors[&edge.v()]);
+        }
+    }
+
+    fn color_count(colors: &NodeHashMap<UnListGraph<(), ()>, usize>) -> usize {
+        colors.values().unique().count()
+    }
+
+    // complete bipartite graph K(n,n) minus a perfect matching. Interleaving the two sides makes
+    // the natural greedy order use n colors
+    fn crown(n: usize) -> UnListGraph<(), ()> {
+        let mut edges = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                if i != j {
+                    edges.push((2 * i, 2 * j + 1, ()));
+                }
+            }
+        }
+        UnListGraph::from_ordinal(vec![(); 2 * n], edges)
+    }
+
+    #[test]
+    fn greedy_odd_cycle() {
+        let graph = UnListGraph::builder()
+            .with_size(5)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 3, ())
+            .edge(3, 4, ())
+            .edge(4, 0, ())
+            .build();
+
+        for order in [
+            ColoringOrder::Natural,
+            ColoringOrder::LargestFirst,
+            ColoringOrder::SmallestLast,
+        ] {
+            let colors = greedy_coloring(&graph, order);
+            assert_proper(&graph, &colors);
+            assert_eq!(color_count(&colors), 3);
+        }
+    }
+
+    #[test]
+    fn greedy_crown_natural_vs_dsatur() {
+        let graph = crown(4);
+
+        let natural = greedy_coloring(&graph, ColoringOrder::Natural);
+        assert_proper(&graph, &natural);
+        assert_eq!(color_count(&natural), 4);
+
+        let colors = dsatur(&graph);
+        assert_proper(&graph, &colors);
+        assert_eq!(color_count(&colors), 2);
+    }
+
+    #[test]
+    fn smallest_last_tree() {
+        // trees are 1-degenerate, so smallest last needs only two colors
+        //       0
+        //     / | \
+        //    1  2  3
+        //   / \    |
+        //  4   5   6
+        let graph = UnListGraph::builder()
+            .with_size(7)
+            .edge(0, 1, ())
+            .edge(0, 2, ())
+            .edge(0, 3, ())
+            .edge(1, 4, ())
+            .edge(1, 5, ())
+            .edge(3, 6, ())
+            .build();
+        let colors = greedy_coloring(&graph, ColoringOrder::SmallestLast);
+        assert_proper(&graph, &colors);
+        assert_eq!(color_count(&colors), 2);
+    }
+
+    #[test]
+    fn bipartite_even_cycle() {
+        let graph = UnListGraph::builder()
+            .with_size(6)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 3, ())
+            .edge(3, 4, ())
+            .edge(4, 5, ())
+            .edge(5, 0, ())
+            .build();
+
+        match is_bipartite(&graph) {
+            Bipartiteness::Bipartite(sides) => {
+                assert_proper(&graph, &sides);
+                assert!(sides.values().all(|&side| side < 2));
+            }
+            Bipartiteness::OddCycle(_) => panic!("even cycle should be bipartite"),
+        }
+    }
+
+    #[test]
+    fn odd_cycle_witness() {
+        // 0 -- 1 -- 2 -- 3
+        //       \        |
+        //  6 --- 5 ----- 4
+        // plus a separate bipartite component 7 -- 8
+        let graph = UnListGraph::builder()
+            .with_size(9)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 3, ())
+            .edge(3, 4, ())
+            .edge(4, 5, ())
+            .edge(5, 1, ())
+            .edge(5, 6, ())
+            .edge(7, 8, ())
+            .build();
+
+        match is_bipartite(&graph) {
+            Bipartiteness::Bipartite(_) => panic!("graph contains a 5-cycle"),
+            Bipartiteness::OddCycle(cycle) => {
+                let nodes = cycle.nodes().map(|node| node.id()).collect_vec();
+                assert_eq!(nodes.first(), nodes.last());
+                assert_eq!(cycle.edges().count(), 5);
+                for (edge, pair) in cycle.edges().zip(nodes.windows(2)) {
+                    assert_eq!(edge.other(pair[0]), pair[1]);
+                }
+                let mut inner = nodes[1..].to_vec();
+                inner.sort();
+                assert_eq!(inner, vec![1, 2, 3, 4, 5]);
+            }
+        }
+    }
+}
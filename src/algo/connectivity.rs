@@ -0,0 +1,407 @@
+use crate::algo::errors::AlgoError;
+use crate::algo::flow::dinic::dinic;
+use crate::graph::flow::FlowGraph;
+use crate::graph::traits::{Graph, GraphIter, OrdinalGraph, WithCapacity};
+use crate::graph::types::{FlowFlatGraph, NodeHashMap};
+use crate::iter::traits::Path;
+
+use ahash::AHashMap;
+
+// The functions in this module work on directed and undirected graphs alike, since every edge
+// returned by `adj` becomes a unit capacity arc in the flow network
+
+// Minimum number of edges whose removal disconnects the graph (for directed graphs: leaves it not
+// strongly connected)
+pub fn edge_connectivity<G>(graph: &G) -> usize
+where
+    G: GraphIter,
+{
+    let mut network = Network::new(graph, false);
+    let n = network.ids.len();
+    if n < 2 {
+        return 0;
+    }
+
+    // every cut separates node 0 from some other node, in one direction or the other
+    let mut best = usize::MAX;
+    for v in 1..n {
+        best = best.min(network.max_flow(0, v)).min(network.max_flow(v, 0));
+        if best == 0 {
+            break;
+        }
+    }
+    best
+}
+
+// Minimum number of nodes whose removal disconnects the graph, n - 1 for complete graphs
+pub fn node_connectivity<G>(graph: &G) -> usize
+where
+    G: GraphIter,
+{
+    let mut network = Network::new(graph, true);
+    let n = network.ids.len();
+    if n < 2 {
+        return 0;
+    }
+
+    // a minimum node cut can't contain all of the first best + 1 nodes, so one of them is on a
+    // side of it (Even's algorithm)
+    let mut best = n - 1;
+    let mut i = 0;
+    while i <= best && i < n {
+        for j in (i + 1)..n {
+            let (u, v) = (network.ids[i], network.ids[j]);
+            if !graph.contains_edge(u, v) {
+                best = best.min(network.max_flow(i, j));
+            }
+            if !graph.contains_edge(v, u) {
+                best = best.min(network.max_flow(j, i));
+            }
+        }
+        i += 1;
+    }
+    best
+}
+
+// Maximum set of paths from s to t that share no edge
+pub fn edge_disjoint_paths<'a, G>(
+    graph: &'a G,
+    s: G::NId,
+    t: G::NId,
+) -> Result<Vec<Path<'a, G>>, AlgoError>
+where
+    G: GraphIter,
+{
+    disjoint_paths(graph, s, t, false)
+}
+
+// Maximum set of paths from s to t that share no node other than s and t
+pub fn node_disjoint_paths<'a, G>(
+    graph: &'a G,
+    s: G::NId,
+    t: G::NId,
+) -> Result<Vec<Path<'a, G>>, AlgoError>
+where
+    G: GraphIter,
+{
+    disjoint_paths(graph, s, t, true)
+}
+
+fn disjoint_paths<'a, G>(
+    graph: &'a G,
+    s: G::NId,
+    t: G::NId,
+    split: bool,
+) -> Result<Vec<Path<'a, G>>, AlgoError>
+where
+    G: GraphIter,
+{
+    let mut network = Network::new(graph, split);
+    let s = *network
+        .index
+        .get(&s)
+        .ok_or_else(|| AlgoError::SourceNotFound(format!("{:?}", s)))?;
+    let t = *network
+        .index
+        .get(&t)
+        .ok_or_else(|| AlgoError::SinkNotFound(format!("{:?}", t)))?;
+    if s == t {
+        return Ok(Vec::new());
+    }
+
+    let count = network.max_flow(s, t);
+    Ok(network
+        .walks(s, t, count)
+        .into_iter()
+        .map(|walk| {
+            let mut path = vec![(None, graph.node(network.ids[s]).unwrap())];
+            for arc in walk {
+                if let Some(edge_id) = network.arcs[arc].edge {
+                    let v = network.arcs[arc].v / network.width();
+                    path.push((graph.edge(edge_id), graph.node(network.ids[v]).unwrap()));
+                }
+            }
+            Path::new(path)
+        })
+        .collect())
+}
+
+// Unit capacity flow network over the nodes of a graph, indexed 0..n. With split, node v becomes
+// an in node 2v and an out node 2v + 1 joined by a unit arc, so flows can pass it only once
+struct Network<G>
+where
+    G: Graph,
+{
+    flow: FlowFlatGraph<(), isize>,
+    ids: Vec<G::NId>,
+    index: NodeHashMap<G, usize>,
+    // forward flow edge 2k is arc k
+    arcs: Vec<NetworkArc<G::EId>>,
+    split: bool,
+}
+
+struct NetworkArc<EId> {
+    u: usize,
+    v: usize,
+    // None for the internal arc of a split node
+    edge: Option<EId>,
+}
+
+impl<G> Network<G>
+where
+    G: GraphIter,
+{
+    fn new(graph: &G, split: bool) -> Self {
+        let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+        let mut index = NodeHashMap::<G, usize>::default();
+        for (i, &id) in ids.iter().enumerate() {
+            index.insert(id, i);
+        }
+
+        let width = if split { 2 } else { 1 };
+        let mut arcs = Vec::new();
+        if split {
+            for v in 0..ids.len() {
+                arcs.push(NetworkArc {
+                    u: 2 * v,
+                    v: 2 * v + 1,
+                    edge: None,
+                });
+            }
+        }
+        for (u, &id) in ids.iter().enumerate() {
+            for (edge, node) in graph.adj(id).unwrap() {
+                let v = index[&node.id()];
+                if u != v {
+                    arcs.push(NetworkArc {
+                        u: width * u + width - 1,
+                        v: width * v,
+                        edge: Some(edge.id()),
+                    });
+                }
+            }
+        }
+
+        let mut flow = FlowFlatGraph::with_capacity(width * ids.len(), 2 * arcs.len());
+        for _ in 0..width * ids.len() {
+            flow.insert_node(());
+        }
+        for arc in &arcs {
+            flow.insert_flow_edge(arc.u, arc.v, 1)
+                .expect("node ids should exist");
+        }
+
+        Network {
+            flow,
+            ids,
+            index,
+            arcs,
+            split,
+        }
+    }
+
+    fn width(&self) -> usize {
+        if self.split {
+            2
+        } else {
+            1
+        }
+    }
+
+    // flow leaves a split node through its out node and enters the sink through its in node
+    fn max_flow(&mut self, s: usize, t: usize) -> usize {
+        let width = self.width();
+        self.flow.reset_flow();
+        dinic(&mut self.flow, width * s + width - 1, width * t).expect("nodes should exist")
+            as usize
+    }
+
+    // Splits the current flow into count arc sequences from s to t, dropping any cycles
+    fn walks(&self, s: usize, t: usize, count: usize) -> Vec<Vec<usize>> {
+        let width = self.width();
+        let mut used: Vec<isize> = (0..self.arcs.len())
+            .map(|k| self.flow.edge(2 * k).unwrap().flow())
+            .collect();
+
+        // an undirected edge yields an arc each way, flow over both of them cancels out
+        let mut by_edge = AHashMap::new();
+        for (k, arc) in self.arcs.iter().enumerate() {
+            if let Some(edge_id) = arc.edge {
+                by_edge.entry(edge_id).or_insert_with(Vec::new).push(k);
+            }
+        }
+        for arcs in by_edge.values() {
+            if let [a, b] = arcs[..] {
+                let cancelled = used[a].min(used[b]);
+                used[a] -= cancelled;
+                used[b] -= cancelled;
+            }
+        }
+
+        let mut out = vec![Vec::new(); width * self.ids.len()];
+        for (k, arc) in self.arcs.iter().enumerate() {
+            out[arc.u].push(k);
+        }
+
+        let (source, sink) = (width * s + width - 1, width * t);
+        let mut walks = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut walk: Vec<usize> = Vec::new();
+            let mut visited_at = AHashMap::from([(source, 0)]);
+            let mut cur = source;
+            while cur != sink {
+                let k = *out[cur]
+                    .iter()
+                    .find(|&&k| used[k] > 0)
+                    .expect("flow should be conserved");
+                used[k] -= 1;
+                cur = self.arcs[k].v;
+                walk.push(k);
+
+                // came back to a node on the walk, so drop the cycle
+                if let Some(&at) = visited_at.get(&cur) {
+                    for dropped in walk.drain(at..) {
+                        visited_at.remove(&self.arcs[dropped].v);
+                    }
+                }
+                visited_at.insert(cur, walk.len());
+            }
+            walks.push(walk);
+        }
+        walks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::connectivity::{
+        edge_connectivity, edge_disjoint_paths, node_connectivity, node_disjoint_paths,
+    };
+    use crate::algo::errors::AlgoError;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::{DiListGraph, UnListGraph};
+    use itertools::Itertools;
+
+    // two triangles 0-1-2 and 3-4-5 sharing node 2 and joined again by the edge 1-4
+    //
+    // 0 --- 1 ----- 4 --- 3
+    //  \   /         \   /
+    //   \ /           \ /
+    //    2 ----------- 5
+    fn bowtie() -> UnListGraph<(), ()> {
+        UnListGraph::builder()
+            .with_size(6)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 0, ())
+            .edge(3, 4, ())
+            .edge(4, 5, ())
+            .edge(5, 3, ())
+            .edge(1, 4, ())
+            .edge(2, 5, ())
+            .build()
+    }
+
+    #[test]
+    fn undirected_connectivity() {
+        let graph = bowtie();
+        assert_eq!(edge_connectivity(&graph), 2);
+        assert_eq!(node_connectivity(&graph), 2);
+
+        let paths = edge_disjoint_paths(&graph, 0, 3).unwrap();
+        assert_eq!(paths.len(), 2);
+        let mut used_edges = paths
+            .iter()
+            .flat_map(|path| path.edges().map(|edge| edge.id()))
+            .collect_vec();
+        let total = used_edges.len();
+        used_edges.sort();
+        used_edges.dedup();
+        assert_eq!(used_edges.len(), total);
+        for path in &paths {
+            let nodes = path.nodes().map(|node| node.id()).collect_vec();
+            assert_eq!((nodes[0], *nodes.last().unwrap()), (0, 3));
+            for (edge, (u, v)) in path.edges().zip(nodes.iter().tuple_windows()) {
+                assert!(edge.u() == *u && edge.v() == *v || edge.u() == *v && edge.v() == *u);
+            }
+        }
+    }
+
+    #[test]
+    fn node_disjoint_needs_distinct_nodes() {
+        // 0 and 4 are joined through 1, 2 and 3, but the two routes via 2 share it
+        //     1
+        //   /   \
+        // 0 - 2 - 4
+        //   \ | /
+        //     3
+        let graph = UnListGraph::builder()
+            .with_size(5)
+            .edge(0, 1, ())
+            .edge(1, 4, ())
+            .edge(0, 2, ())
+            .edge(2, 4, ())
+            .edge(0, 3, ())
+            .edge(3, 4, ())
+            .edge(2, 3, ())
+            .build();
+        let paths = node_disjoint_paths(&graph, 0, 4).unwrap();
+        assert_eq!(paths.len(), 3);
+        let inner = paths
+            .iter()
+            .flat_map(|path| {
+                let nodes = path.nodes().map(|node| node.id()).collect_vec();
+                nodes[1..nodes.len() - 1].to_vec()
+            })
+            .sorted()
+            .collect_vec();
+        assert_eq!(inner, vec![1, 2, 3]);
+        // node 1 only has two neighbors
+        assert_eq!(node_connectivity(&graph), 2);
+
+        let star = UnListGraph::from_ordinal(vec![(); 4], vec![(0, 1, ()), (0, 2, ()), (0, 3, ())]);
+        assert_eq!(node_connectivity(&star), 1);
+        assert_eq!(node_disjoint_paths(&star, 1, 2).unwrap().len(), 1);
+        assert_eq!(edge_disjoint_paths(&star, 1, 1).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn directed_connectivity() {
+        // a directed cycle is strongly connected but a single edge breaks it
+        let cycle = DiListGraph::from_ordinal(
+            vec![(); 4],
+            vec![(0, 1, ()), (1, 2, ()), (2, 3, ()), (3, 0, ())],
+        );
+        assert_eq!(edge_connectivity(&cycle), 1);
+        assert_eq!(node_connectivity(&cycle), 1);
+
+        let mut edges = Vec::new();
+        for (u, v) in (0..4).tuple_combinations() {
+            edges.push((u, v, ()));
+            edges.push((v, u, ()));
+        }
+        let complete = DiListGraph::from_ordinal(vec![(); 4], edges);
+        assert_eq!(edge_connectivity(&complete), 3);
+        assert_eq!(node_connectivity(&complete), 3);
+
+        let paths = edge_disjoint_paths(&cycle, 3, 1).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(
+            paths[0].nodes().map(|node| node.id()).collect_vec(),
+            vec![3, 0, 1]
+        );
+    }
+
+    #[test]
+    fn missing_nodes() {
+        let graph = bowtie();
+        assert!(matches!(
+            edge_disjoint_paths(&graph, 9, 0),
+            Err(AlgoError::SourceNotFound(..))
+        ));
+        assert!(matches!(
+            node_disjoint_paths(&graph, 0, 9),
+            Err(AlgoError::SinkNotFound(..))
+        ));
+    }
+}
@@ -0,0 +1,538 @@
+use crate::graph::edge::Edge;
+use crate::graph::traits::{DirectedGraph, Graph, GraphIter, UndirectedGraph};
+use crate::graph::types::NodeHashMap;
+use crate::iter::bfs::bfs;
+
+use ahash::AHashSet;
+
+pub type Cycle<'a, G> = Vec<Edge<'a, <G as Graph>::NId, <G as Graph>::EId, <G as Graph>::E>>;
+
+// Johnson's algorithm. Lazily yields every elementary cycle as the list of edges walked, so
+// parallel edges produce distinct cycles and a self loop is a cycle of one edge
+pub fn simple_cycles<'a, G>(graph: &'a G) -> SimpleCycles<'a, G>
+where
+    G: DirectedGraph + GraphIter,
+{
+    SimpleCycles::new(graph)
+}
+
+// Returns some directed cycle, or None if the graph is acyclic
+pub fn find_cycle<'a, G>(graph: &'a G) -> Option<Cycle<'a, G>>
+where
+    G: DirectedGraph + GraphIter,
+{
+    // nodes are on the dfs stack while in `active`, and done once in `finished`
+    let mut active = NodeHashMap::<G, ()>::default();
+    let mut finished = NodeHashMap::<G, ()>::default();
+    let mut parent = NodeHashMap::<G, G::EId>::default();
+
+    for root in graph.nodes() {
+        if finished.contains_key(&root.id()) {
+            continue;
+        }
+        active.insert(root.id(), ());
+        let mut stack = vec![(root.id(), graph.out_edges(root.id()).unwrap())];
+
+        while let Some((u, adj)) = stack.last_mut() {
+            let u = *u;
+            match adj.next() {
+                Some((edge, node)) => {
+                    let v = node.id();
+                    if active.contains_key(&v) {
+                        let mut cycle = vec![];
+                        let mut w = u;
+                        while w != v {
+                            let parent_edge = graph.edge(parent[&w]).unwrap();
+                            w = parent_edge.u();
+                            cycle.push(parent_edge);
+                        }
+                        cycle.reverse();
+                        cycle.push(edge);
+                        return Some(cycle);
+                    }
+                    if !finished.contains_key(&v) {
+                        active.insert(v, ());
+                        parent.insert(v, edge.id());
+                        stack.push((v, graph.out_edges(v).unwrap()));
+                    }
+                }
+                None => {
+                    active.remove(&u);
+                    finished.insert(u, ());
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// Fundamental cycles of a spanning forest: one cycle for every edge outside the forest, made of
+// that edge and the forest path between its endpoints. Any cycle of the graph is a symmetric
+// difference of these
+pub fn cycle_basis<'a, G>(graph: &'a G) -> Vec<Cycle<'a, G>>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let mut depth = NodeHashMap::<G, usize>::default();
+    let mut parent = NodeHashMap::<G, Option<G::EId>>::default();
+    let mut tree_edges = AHashSet::new();
+
+    for root in graph.nodes() {
+        if depth.contains_key(&root.id()) {
+            continue;
+        }
+        for (edge, node) in bfs(graph, root.id()) {
+            let id = node.id();
+            let d = edge.as_ref().map_or(0, |e| depth[&e.other(id)] + 1);
+            depth.insert(id, d);
+            if let Some(e) = edge.as_ref() {
+                tree_edges.insert(e.id());
+            }
+            parent.insert(id, edge.map(|e| e.id()));
+        }
+    }
+
+    graph
+        .edges()
+        .filter(|edge| !tree_edges.contains(&edge.id()))
+        .map(|edge| fundamental_cycle(graph, &depth, &parent, edge))
+        .collect()
+}
+
+// Walks from the lowest common ancestor of the edge's endpoints down to u, across the edge, and
+// from v back up to the ancestor
+fn fundamental_cycle<'a, G>(
+    graph: &'a G,
+    depth: &NodeHashMap<G, usize>,
+    parent: &NodeHashMap<G, Option<G::EId>>,
+    edge: Edge<'a, G::NId, G::EId, G::E>,
+) -> Cycle<'a, G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let up = |id: G::NId| {
+        let parent_edge = graph
+            .edge(parent[&id].expect("non-root node should have a parent edge"))
+            .unwrap();
+        let next = parent_edge.other(id);
+        (parent_edge, next)
+    };
+
+    let (mut a, mut b) = (edge.u(), edge.v());
+    let mut down_to_u = Vec::new();
+    let mut up_from_v = Vec::new();
+    while depth[&a] > depth[&b] {
+        let (e, p) = up(a);
+        down_to_u.push(e);
+        a = p;
+    }
+    while depth[&b] > depth[&a] {
+        let (e, p) = up(b);
+        up_from_v.push(e);
+        b = p;
+    }
+    while a != b {
+        let (ea, pa) = up(a);
+        down_to_u.push(ea);
+        a = pa;
+        let (eb, pb) = up(b);
+        up_from_v.push(eb);
+        b = pb;
+    }
+
+    down_to_u.reverse();
+    down_to_u.push(edge);
+    down_to_u.append(&mut up_from_v);
+    down_to_u
+}
+
+pub struct SimpleCycles<'a, G>
+where
+    G: DirectedGraph + GraphIter,
+{
+    graph: &'a G,
+    // out edges as (edge id, node index) pairs
+    out: Vec<Vec<(G::EId, usize)>>,
+    // strongly connected components that still contain cycles
+    components: Vec<Vec<usize>>,
+    search: Option<Search<G::EId>>,
+}
+
+// State of the circuit search from the smallest node of one component. Nodes on the current
+// path are blocked until some cycle through them is found, and b[w] holds the nodes to unblock
+// together with w
+struct Search<EId> {
+    start: usize,
+    component: Vec<usize>,
+    in_component: Vec<bool>,
+    blocked: Vec<bool>,
+    b: Vec<Vec<usize>>,
+    closed: Vec<bool>,
+    stack: Vec<Frame<EId>>,
+}
+
+struct Frame<EId> {
+    node: usize,
+    remaining: Vec<(EId, usize)>,
+    entered_by: Option<EId>,
+}
+
+impl<'a, G> Iterator for SimpleCycles<'a, G>
+where
+    G: DirectedGraph + GraphIter,
+{
+    type Item = Cycle<'a, G>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let search = match self.search.as_mut() {
+                Some(search) => search,
+                None => {
+                    let component = self.components.pop()?;
+                    self.search = Some(Search::new(component, &self.out));
+                    continue;
+                }
+            };
+
+            let top = match search.stack.last_mut() {
+                Some(top) => top,
+                None => {
+                    // every cycle through start has been found, so drop it and search what is
+                    // left of its component
+                    let search = self.search.take().unwrap();
+                    let rest: Vec<usize> = search
+                        .component
+                        .into_iter()
+                        .filter(|&v| v != search.start)
+                        .collect();
+                    let mut in_rest = vec![false; self.out.len()];
+                    for &v in &rest {
+                        in_rest[v] = true;
+                    }
+                    self.components
+                        .extend(cyclic_components(&self.out, &rest, &in_rest));
+                    continue;
+                }
+            };
+            let this = top.node;
+
+            match top.remaining.pop() {
+                Some((edge_id, next)) if next == search.start => {
+                    for frame in &search.stack {
+                        search.closed[frame.node] = true;
+                    }
+                    let cycle = search
+                        .stack
+                        .iter()
+                        .filter_map(|frame| frame.entered_by)
+                        .chain([edge_id])
+                        .map(|id| self.graph.edge(id).unwrap())
+                        .collect();
+                    return Some(cycle);
+                }
+                Some((edge_id, next)) => {
+                    if !search.blocked[next] {
+                        let remaining = search.neighbors(next, &self.out);
+                        search.stack.push(Frame {
+                            node: next,
+                            remaining,
+                            entered_by: Some(edge_id),
+                        });
+                        search.closed[next] = false;
+                        search.blocked[next] = true;
+                    }
+                }
+                None => {
+                    if search.closed[this] {
+                        search.unblock(this);
+                    } else {
+                        for &(_, w) in &self.out[this] {
+                            if search.in_component[w] && !search.b[w].contains(&this) {
+                                search.b[w].push(this);
+                            }
+                        }
+                    }
+                    search.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<'a, G> SimpleCycles<'a, G>
+where
+    G: DirectedGraph + GraphIter,
+{
+    fn new(graph: &'a G) -> Self {
+        let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+        let mut index = NodeHashMap::<G, usize>::default();
+        for (i, &id) in ids.iter().enumerate() {
+            index.insert(id, i);
+        }
+        let out: Vec<Vec<(G::EId, usize)>> = ids
+            .iter()
+            .map(|&id| {
+                graph
+                    .out_edges(id)
+                    .unwrap()
+                    .map(|(edge, node)| (edge.id(), index[&node.id()]))
+                    .collect()
+            })
+            .collect();
+
+        let all: Vec<usize> = (0..ids.len()).collect();
+        let everything = vec![true; ids.len()];
+        let components = cyclic_components(&out, &all, &everything);
+
+        SimpleCycles {
+            graph,
+            out,
+            components,
+            search: None,
+        }
+    }
+}
+
+impl<EId: Copy> Search<EId> {
+    fn new(component: Vec<usize>, out: &[Vec<(EId, usize)>]) -> Self {
+        let n = out.len();
+        let start = *component.iter().min().unwrap();
+        let mut in_component = vec![false; n];
+        for &v in &component {
+            in_component[v] = true;
+        }
+
+        let mut search = Search {
+            start,
+            component,
+            in_component,
+            blocked: vec![false; n],
+            b: vec![Vec::new(); n],
+            closed: vec![false; n],
+            stack: Vec::new(),
+        };
+        let remaining = search.neighbors(start, out);
+        search.stack.push(Frame {
+            node: start,
+            remaining,
+            entered_by: None,
+        });
+        search.blocked[start] = true;
+        search
+    }
+
+    // reversed so popping visits out edges in graph order
+    fn neighbors(&self, v: usize, out: &[Vec<(EId, usize)>]) -> Vec<(EId, usize)> {
+        out[v]
+            .iter()
+            .rev()
+            .filter(|(_, w)| self.in_component[*w])
+            .copied()
+            .collect()
+    }
+
+    fn unblock(&mut self, v: usize) {
+        let mut pending = vec![v];
+        while let Some(w) = pending.pop() {
+            if self.blocked[w] {
+                self.blocked[w] = false;
+                pending.append(&mut self.b[w]);
+            }
+        }
+    }
+}
+
+// Tarjan's algorithm restricted to `nodes`, keeping only components that contain a cycle
+fn cyclic_components<EId>(
+    out: &[Vec<(EId, usize)>],
+    nodes: &[usize],
+    allowed: &[bool],
+) -> Vec<Vec<usize>> {
+    let n = out.len();
+    let mut order = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut time = 0;
+
+    for &root in nodes {
+        if order[root] != usize::MAX {
+            continue;
+        }
+        // (node, index of next out edge to visit)
+        let mut call_stack = vec![(root, 0)];
+        order[root] = time;
+        low[root] = time;
+        time += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&mut (v, ref mut i)) = call_stack.last_mut() {
+            if let Some(&(_, w)) = out[v].get(*i) {
+                *i += 1;
+                if !allowed[w] {
+                    continue;
+                }
+                if order[w] == usize::MAX {
+                    order[w] = time;
+                    low[w] = time;
+                    time += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    call_stack.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(order[w]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == order[v] {
+                let mut component = Vec::new();
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                let self_loop = out[v].iter().any(|&(_, w)| w == v);
+                if component.len() > 1 || self_loop {
+                    components.push(component);
+                }
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::cycles::{cycle_basis, find_cycle, simple_cycles};
+    use crate::graph::edge::Edge;
+    use crate::graph::traits::{GraphMut, KeyedGraph, OrdinalGraph};
+    use crate::graph::types::{DiListGraph, DiMapGraph, UnListGraph};
+    use itertools::Itertools;
+
+    fn assert_undirected_cycle(cycle: &[Edge<usize, usize, ()>]) {
+        let (first, second) = (&cycle[0], &cycle[cycle.len() - 1]);
+        let start = match second.u() == first.u() || second.v() == first.u() {
+            true => first.u(),
+            false => first.v(),
+        };
+        let mut at = start;
+        for edge in cycle {
+            at = edge.other(at);
+        }
+        assert_eq!(at, start);
+    }
+
+    #[test]
+    fn johnson_small_digraph() {
+        // 0 -> 1 -> 2 -> 0, 1 -> 0, 2 -> 2, 2 -> 3 -> 1
+        let graph = DiListGraph::builder()
+            .with_size(4)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 0, ())
+            .edge(1, 0, ())
+            .edge(2, 2, ())
+            .edge(2, 3, ())
+            .edge(3, 1, ())
+            .build();
+
+        let cycles = simple_cycles(&graph).collect_vec();
+        for cycle in &cycles {
+            for (a, b) in cycle.iter().circular_tuple_windows() {
+                assert_eq!(a.v(), b.u());
+            }
+        }
+        let mut node_sets = cycles
+            .iter()
+            .map(|cycle| cycle.iter().map(|edge| edge.u()).sorted().collect_vec())
+            .collect_vec();
+        node_sets.sort();
+        assert_eq!(
+            node_sets,
+            vec![vec![0, 1], vec![0, 1, 2], vec![1, 2, 3], vec![2]]
+        );
+    }
+
+    #[test]
+    fn johnson_complete_digraph() {
+        // complete digraph on n nodes has sum_{k=2..n} C(n,k) * (k-1)! elementary cycles
+        let mut graph = DiListGraph::builder().with_size(5).build();
+        for (u, v) in (0..5).tuple_combinations() {
+            graph.insert_edge(u, v, ()).expect("nodes should exist");
+            graph.insert_edge(v, u, ()).expect("nodes should exist");
+        }
+        assert_eq!(simple_cycles(&graph).count(), 10 + 20 + 30 + 24);
+    }
+
+    #[test]
+    fn johnson_acyclic() {
+        let graph = DiListGraph::builder()
+            .with_size(4)
+            .edge(0, 1, ())
+            .edge(0, 2, ())
+            .edge(1, 3, ())
+            .edge(2, 3, ())
+            .build();
+        assert_eq!(simple_cycles(&graph).count(), 0);
+        assert!(find_cycle(&graph).is_none());
+    }
+
+    #[test]
+    fn find_cycle_witness() {
+        let mut graph = DiMapGraph::new();
+        for name in ["a", "b", "c", "d", "e"] {
+            graph.put_node(name, ());
+        }
+        graph.insert_edge("a", "b", ()).expect("nodes should exist");
+        graph.insert_edge("b", "c", ()).expect("nodes should exist");
+        graph.insert_edge("c", "d", ()).expect("nodes should exist");
+        graph.insert_edge("d", "b", ()).expect("nodes should exist");
+        graph.insert_edge("d", "e", ()).expect("nodes should exist");
+
+        let cycle = find_cycle(&graph).expect("graph has a cycle");
+        let nodes = cycle.iter().map(|edge| edge.u()).sorted().collect_vec();
+        assert_eq!(nodes, vec!["b", "c", "d"]);
+        for (a, b) in cycle.iter().circular_tuple_windows() {
+            assert_eq!(a.v(), b.u());
+        }
+    }
+
+    #[test]
+    fn basis_of_two_squares() {
+        // 0 --- 1 --- 2
+        // |     |     |
+        // 3 --- 4 --- 5     6 --- 7
+        let graph = UnListGraph::builder()
+            .with_size(8)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(0, 3, ())
+            .edge(1, 4, ())
+            .edge(2, 5, ())
+            .edge(3, 4, ())
+            .edge(4, 5, ())
+            .edge(6, 7, ())
+            .build();
+
+        // m - n + components = 8 - 8 + 2
+        let basis = cycle_basis(&graph);
+        assert_eq!(basis.len(), 2);
+        for cycle in &basis {
+            assert_undirected_cycle(cycle);
+            assert_eq!(cycle.len(), 4);
+        }
+    }
+}
@@ -8,6 +8,12 @@ pub enum AlgoError {
     #[error("Start node {0:?} does not exist in the graph")]
     StartNodeNotFound(String),
 
+    #[error("Target node {0:?} does not exist in the graph")]
+    TargetNodeNotFound(String),
+
+    #[error("Edge {0:?} does not exist in the graph")]
+    EdgeNotFound(String),
+
     #[error("No path from start node {0:?} to end node {1:?}")]
     NoPathFromStartToEnd(String, String),
 
@@ -17,6 +23,15 @@ pub enum AlgoError {
     #[error("Sink node {0:?} does not exist in the flow graph")]
     SinkNotFound(String),
 
+    #[error("No flow satisfies the lower bounds and demands")]
+    InfeasibleFlow,
+
+    #[error("Edge weights are too large to compute with in their type without overflow")]
+    WeightOverflow,
+
+    #[error("Inflation {0} has to be a fraction num / den of at least 1 with den above 0")]
+    InvalidInflation(String),
+
     #[error("unimplemented")]
     Unimplemented,
 }
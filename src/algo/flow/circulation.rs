@@ -0,0 +1,268 @@
+use crate::algo::errors::AlgoError;
+use crate::algo::flow::dinic::dinic;
+use crate::graph::flow::FlowGraph;
+use crate::graph::traits::{Graph, GraphIter, OrdinalGraph, WithCapacity};
+use crate::graph::types::{FlowFlatGraph, NodeHashMap};
+
+// Finds flows within the bounds of every edge such that each node v receives demand(v) more flow
+// than it sends, where negative demands are supplies. On success the flows are stored in the graph,
+// otherwise the graph's flow is reset
+pub fn feasible_circulation<G, D>(graph: &mut G, demand: D) -> Result<(), AlgoError>
+where
+    G: FlowGraph + GraphIter,
+    D: Fn(G::NId) -> G::FlowVal,
+{
+    feasible_flow(graph, demand, None)
+}
+
+// Maximum s-t flow that respects the lower bound of every edge. The flow is stored in the graph
+pub fn max_flow_with_lower_bounds<G>(
+    graph: &mut G,
+    source: G::NId,
+    sink: G::NId,
+) -> Result<G::FlowVal, AlgoError>
+where
+    G: FlowGraph + GraphIter,
+{
+    if !graph.contains_node(source) {
+        return Err(AlgoError::SourceNotFound(format!("{:?}", source)));
+    }
+    if !graph.contains_node(sink) {
+        return Err(AlgoError::SinkNotFound(format!("{:?}", sink)));
+    }
+
+    // any feasible s-t flow is a circulation once the sink can send flow back to the source, and
+    // augmenting from it never undercuts a lower bound because bounded back edges forbid that
+    feasible_flow(graph, |_| G::FlowVal::default(), Some((source, sink)))?;
+    dinic(graph, source, sink)?;
+
+    Ok(graph
+        .forward_edges()
+        .fold(G::FlowVal::default(), |acc, edge| {
+            match (edge.u() == source, edge.v() == source) {
+                (true, false) => acc + edge.flow(),
+                (false, true) => acc - edge.flow(),
+                _ => acc,
+            }
+        }))
+}
+
+// Reduces lower bounds and demands to a max flow from a super source to a super sink over
+// the slack between lower bound and capacity
+fn feasible_flow<G, D>(
+    graph: &mut G,
+    demand: D,
+    sink_to_source: Option<(G::NId, G::NId)>,
+) -> Result<(), AlgoError>
+where
+    G: FlowGraph + GraphIter,
+    D: Fn(G::NId) -> G::FlowVal,
+{
+    let zero = G::FlowVal::default();
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+    let n = ids.len();
+    let (super_source, super_sink) = (n, n + 1);
+
+    // net inflow each node still needs once every edge carries its lower bound
+    let mut balance: Vec<G::FlowVal> = ids.iter().map(|&id| demand(id)).collect();
+    let mut slack = FlowFlatGraph::<(), G::FlowVal>::with_capacity(n + 2, 2 * graph.m() + 2 * n);
+    for _ in 0..n + 2 {
+        slack.insert_node(());
+    }
+
+    let mut total_capacity = zero;
+    let mut edges = Vec::new();
+    for edge in graph.forward_edges() {
+        let (u, v) = (index[&edge.u()], index[&edge.v()]);
+        balance[v] = balance[v] - edge.lower();
+        balance[u] = balance[u] + edge.lower();
+        total_capacity = total_capacity + edge.capacity();
+
+        let (slack_id, _) = slack
+            .insert_flow_edge(u, v, edge.capacity() - edge.lower())
+            .expect("node ids should exist");
+        edges.push((edge.id(), edge.lower(), slack_id));
+    }
+    if let Some((source, sink)) = sink_to_source {
+        slack
+            .insert_flow_edge(index[&sink], index[&source], total_capacity)
+            .expect("node ids should exist");
+    }
+
+    let (mut required, mut supplied) = (zero, zero);
+    for (v, &b) in balance.iter().enumerate() {
+        if b > zero {
+            slack
+                .insert_flow_edge(v, super_sink, b)
+                .expect("node ids should exist");
+            required = required + b;
+        } else if b < zero {
+            slack
+                .insert_flow_edge(super_source, v, -b)
+                .expect("node ids should exist");
+            supplied = supplied - b;
+        }
+    }
+
+    graph.reset_flow();
+    if required != supplied || dinic(&mut slack, super_source, super_sink)? != required {
+        return Err(AlgoError::InfeasibleFlow);
+    }
+
+    for (edge_id, lower, slack_id) in edges {
+        let flow = lower + slack.edge(slack_id).unwrap().flow();
+        if flow != zero {
+            graph
+                .increase_flow(edge_id, flow)
+                .expect("flow should be within capacity");
+        }
+        if lower != zero {
+            graph
+                .back_edge_mut(edge_id)
+                .expect("back edge should exist")
+                .data()
+                .hold_lower_bound(lower);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::flow::circulation::{feasible_circulation, max_flow_with_lower_bounds};
+    use crate::algo::flow::dinic::dinic;
+    use crate::graph::flow::FlowGraph;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::FlowFlatGraph;
+
+    fn net_inflow(graph: &FlowFlatGraph<(), i32>, v: usize) -> i32 {
+        graph
+            .forward_edges()
+            .map(|edge| match (edge.u() == v, edge.v() == v) {
+                (false, true) => edge.flow(),
+                (true, false) => -edge.flow(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn within_bounds(graph: &FlowFlatGraph<(), i32>) -> bool {
+        graph
+            .forward_edges()
+            .all(|edge| edge.lower() <= edge.flow() && edge.flow() <= edge.capacity())
+    }
+
+    #[test]
+    fn warehouse_balancing() {
+        // warehouses 0 and 1 hold surplus stock, stores 2 and 3 need it
+        let mut graph = FlowFlatGraph::new();
+        for _ in 0..4 {
+            graph.insert_node(());
+        }
+        graph
+            .insert_flow_edge(0, 2, 4)
+            .expect("node ids should exist");
+        graph
+            .insert_flow_edge(0, 3, 2)
+            .expect("node ids should exist");
+        graph
+            .insert_bounded_flow_edge(1, 2, 1, 3)
+            .expect("node ids should exist");
+        graph
+            .insert_flow_edge(1, 3, 5)
+            .expect("node ids should exist");
+        graph
+            .insert_flow_edge(2, 3, 1)
+            .expect("node ids should exist");
+
+        let demands = [-5, -3, 4, 4];
+        feasible_circulation(&mut graph, |v| demands[v]).expect("circulation should exist");
+        assert!(within_bounds(&graph));
+        for (v, &demand) in demands.iter().enumerate() {
+            assert_eq!(net_inflow(&graph, v), demand);
+        }
+
+        // stores want more than the warehouses hold
+        let demands = [-5, -3, 5, 4];
+        assert_eq!(
+            feasible_circulation(&mut graph, |v| demands[v]),
+            Err(AlgoError::InfeasibleFlow)
+        );
+    }
+
+    #[test]
+    fn lower_bounds_limit_max_flow() {
+        // 0 = s, 1 = a, 2 = b, 3 = t, and a must pass 2 units on to b
+        let mut graph = FlowFlatGraph::new();
+        for _ in 0..4 {
+            graph.insert_node(());
+        }
+        graph
+            .insert_flow_edge(0, 1, 5)
+            .expect("node ids should exist");
+        graph
+            .insert_flow_edge(1, 3, 5)
+            .expect("node ids should exist");
+        graph
+            .insert_bounded_flow_edge(1, 2, 2, 2)
+            .expect("node ids should exist");
+        graph
+            .insert_flow_edge(0, 2, 3)
+            .expect("node ids should exist");
+        let (bt, _) = graph
+            .insert_flow_edge(2, 3, 3)
+            .expect("node ids should exist");
+
+        assert_eq!(max_flow_with_lower_bounds(&mut graph, 0, 3), Ok(6));
+        assert!(within_bounds(&graph));
+        assert_eq!(net_inflow(&graph, 1), 0);
+        assert_eq!(net_inflow(&graph, 2), 0);
+
+        // b can't get rid of the forced flow anymore
+        graph.remove_flow_edge(bt).expect("edge should exist");
+        graph
+            .insert_flow_edge(2, 3, 1)
+            .expect("node ids should exist");
+        assert_eq!(
+            max_flow_with_lower_bounds(&mut graph, 0, 3),
+            Err(AlgoError::InfeasibleFlow)
+        );
+    }
+
+    #[test]
+    fn invalid_bounds() {
+        let mut graph = FlowFlatGraph::<(), i32>::new();
+        graph.insert_node(());
+        graph.insert_node(());
+        assert!(graph.insert_bounded_flow_edge(0, 1, 3, 2).is_err());
+    }
+
+    #[test]
+    fn max_flow_ignores_lower_bounds() {
+        let mut graph = FlowFlatGraph::<(), i32>::new();
+        for _ in 0..3 {
+            graph.insert_node(());
+        }
+        graph
+            .insert_flow_edge(0, 1, 1)
+            .expect("node ids should exist");
+        graph
+            .insert_bounded_flow_edge(1, 2, 2, 5)
+            .expect("node ids should exist");
+        assert_eq!(dinic(&mut graph, 0, 2), Ok(1));
+
+        // lower bounds held by a circulation are let go of when the flow is reset
+        graph
+            .insert_flow_edge(2, 1, 3)
+            .expect("node ids should exist");
+        feasible_circulation(&mut graph, |_| 0).expect("circulation should exist");
+        assert!(within_bounds(&graph));
+        graph.reset_flow();
+        assert_eq!(dinic(&mut graph, 0, 2), Ok(1));
+    }
+}
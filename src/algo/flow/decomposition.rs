@@ -0,0 +1,277 @@
+use crate::algo::cycles::Cycle;
+use crate::algo::errors::AlgoError;
+use crate::graph::flow::{FlowGraph, FlowValue};
+use crate::graph::traits::{Graph, GraphIter};
+use crate::graph::types::NodeHashMap;
+use crate::iter::traits::Path;
+
+use ahash::AHashMap;
+
+pub struct FlowDecomposition<'a, G>
+where
+    G: 'a + FlowGraph,
+{
+    pub paths: Vec<(Path<'a, G>, G::FlowVal)>,
+    pub cycles: Vec<(Cycle<'a, G>, G::FlowVal)>,
+}
+
+// Splits the flow on the forward edges of a flow graph into source-sink paths and cycles, each
+// carrying an amount, such that summing them gives back the flow on every edge. Flow is expected
+// to be conserved at every node other than source and sink
+pub fn decompose_flow<'a, G>(
+    graph: &'a G,
+    source: G::NId,
+    sink: G::NId,
+) -> Result<FlowDecomposition<'a, G>, AlgoError>
+where
+    G: FlowGraph + GraphIter,
+{
+    if !graph.contains_node(source) {
+        return Err(AlgoError::SourceNotFound(format!("{:?}", source)));
+    }
+    if !graph.contains_node(sink) {
+        return Err(AlgoError::SinkNotFound(format!("{:?}", sink)));
+    }
+
+    let mut decomposer = Decomposer::new(graph);
+    let mut decomposition = FlowDecomposition {
+        paths: Vec::new(),
+        cycles: Vec::new(),
+    };
+
+    if source != sink {
+        while let Some(walk) = decomposer.walk(source, Some(sink), &mut decomposition.cycles) {
+            let amount = decomposer.take(&walk);
+            let mut path = vec![(None, graph.node(source).unwrap())];
+            for (edge_id, v) in walk {
+                path.push((graph.edge(edge_id), graph.node(v).unwrap()));
+            }
+            decomposition.paths.push((Path::new(path), amount));
+        }
+    }
+
+    // whatever is left circulates
+    for node in graph.nodes() {
+        while decomposer
+            .walk(node.id(), None, &mut decomposition.cycles)
+            .is_some()
+        {}
+    }
+
+    Ok(decomposition)
+}
+
+// (edge, node it leads to) steps along forward edges
+type Steps<G> = Vec<(<G as Graph>::EId, <G as Graph>::NId)>;
+
+struct Decomposer<'a, G>
+where
+    G: FlowGraph,
+{
+    graph: &'a G,
+    remaining: AHashMap<G::EId, G::FlowVal>,
+    // forward edges carrying flow out of each node, exhausted edges are skipped past
+    out: NodeHashMap<G, Steps<G>>,
+    next: NodeHashMap<G, usize>,
+}
+
+impl<'a, G> Decomposer<'a, G>
+where
+    G: FlowGraph + GraphIter,
+{
+    fn new(graph: &'a G) -> Self {
+        let mut remaining = AHashMap::new();
+        let mut out = NodeHashMap::<G, Steps<G>>::default();
+        let mut next = NodeHashMap::<G, usize>::default();
+        for node in graph.nodes() {
+            let edges = graph
+                .forward_adj(node.id())
+                .unwrap()
+                .filter(|(edge, _)| edge.flow().is_positive())
+                .map(|(edge, other)| {
+                    remaining.insert(edge.id(), edge.flow());
+                    (edge.id(), other.id())
+                })
+                .collect();
+            out.insert(node.id(), edges);
+            next.insert(node.id(), 0);
+        }
+
+        Decomposer {
+            graph,
+            remaining,
+            out,
+            next,
+        }
+    }
+
+    fn next_edge(&mut self, u: G::NId) -> Option<(G::EId, G::NId)> {
+        let edges = &self.out[&u];
+        let i = self.next.get_mut(&u).unwrap();
+        while *i < edges.len() && !self.remaining[&edges[*i].0].is_positive() {
+            *i += 1;
+        }
+        edges.get(*i).copied()
+    }
+
+    // Follows flow from start until reaching target (or start again when there is no target).
+    // Cycles closed along the way are taken out of the flow and recorded. Returns None when
+    // start has no flow left or the walk gets stuck
+    fn walk(
+        &mut self,
+        start: G::NId,
+        target: Option<G::NId>,
+        cycles: &mut Vec<(Cycle<'a, G>, G::FlowVal)>,
+    ) -> Option<Steps<G>> {
+        let mut walk: Steps<G> = Vec::new();
+        let mut position = NodeHashMap::<G, usize>::default();
+        position.insert(start, 0);
+        let mut cur = start;
+
+        loop {
+            if Some(cur) == target {
+                return Some(walk);
+            }
+            let (edge_id, v) = self.next_edge(cur)?;
+            walk.push((edge_id, v));
+            cur = v;
+
+            if let Some(&at) = position.get(&v) {
+                let cycle = walk.split_off(at);
+                for (_, w) in &cycle {
+                    position.remove(w);
+                }
+                position.insert(v, at);
+
+                let amount = self.take(&cycle);
+                let edges = cycle
+                    .into_iter()
+                    .map(|(edge_id, _)| self.graph.edge(edge_id).unwrap())
+                    .collect();
+                cycles.push((edges, amount));
+                if target.is_none() && walk.is_empty() {
+                    return Some(walk);
+                }
+            } else {
+                position.insert(v, walk.len());
+            }
+        }
+    }
+
+    // Removes the bottleneck amount of flow along the walk and returns it
+    fn take(&mut self, walk: &[(G::EId, G::NId)]) -> G::FlowVal {
+        let amount = walk
+            .iter()
+            .map(|(edge_id, _)| self.remaining[edge_id])
+            .min()
+            .unwrap_or_default();
+        for (edge_id, _) in walk {
+            let flow = self.remaining.get_mut(edge_id).unwrap();
+            *flow = *flow - amount;
+        }
+        amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::flow::decomposition::decompose_flow;
+    use crate::algo::flow::dinic::dinic;
+    use crate::graph::flow::FlowGraph;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::FlowFlatGraph;
+    use ahash::AHashMap;
+    use itertools::Itertools;
+
+    fn network() -> FlowFlatGraph<(), i32> {
+        let mut graph = FlowFlatGraph::new();
+        for _ in 0..6 {
+            graph.insert_node(());
+        }
+        for (u, v, c) in [
+            (0, 1, 10),
+            (0, 3, 8),
+            (1, 3, 2),
+            (1, 2, 5),
+            (3, 4, 10),
+            (4, 2, 8),
+            (2, 5, 7),
+            (4, 5, 10),
+        ] {
+            graph
+                .insert_flow_edge(u, v, c)
+                .expect("node ids should exist");
+        }
+        graph
+    }
+
+    #[test]
+    fn max_flow_paths_add_up() {
+        let mut graph = network();
+        let flow = dinic(&mut graph, 0, 5).expect("flow should exist");
+        let decomposition = decompose_flow(&graph, 0, 5).unwrap();
+
+        assert_eq!(
+            decomposition.paths.iter().map(|(_, a)| a).sum::<i32>(),
+            flow
+        );
+        let mut per_edge = AHashMap::new();
+        for (path, amount) in &decomposition.paths {
+            let nodes = path.nodes().map(|node| node.id()).collect_vec();
+            assert_eq!((nodes[0], *nodes.last().unwrap()), (0, 5));
+            assert!(nodes.iter().all_unique());
+            for edge in path.edges() {
+                *per_edge.entry(edge.id()).or_insert(0) += amount;
+            }
+        }
+        for (cycle, amount) in &decomposition.cycles {
+            assert!(*amount > 0);
+            for edge in cycle {
+                *per_edge.entry(edge.id()).or_insert(0) += amount;
+            }
+        }
+        for edge in graph.forward_edges() {
+            assert_eq!(per_edge.get(&edge.id()).copied().unwrap_or(0), edge.flow());
+        }
+    }
+
+    #[test]
+    fn circulating_flow_becomes_cycles() {
+        // one unit along 0 -> 1 -> 3 and two units around 1 -> 2 -> 1
+        let mut graph = FlowFlatGraph::<(), i32>::new();
+        for _ in 0..4 {
+            graph.insert_node(());
+        }
+        let (a, _) = graph.insert_flow_edge(0, 1, 5).unwrap();
+        let (b, _) = graph.insert_flow_edge(1, 2, 5).unwrap();
+        let (c, _) = graph.insert_flow_edge(2, 1, 5).unwrap();
+        let (d, _) = graph.insert_flow_edge(1, 3, 5).unwrap();
+        graph.increase_flow(a, 1).unwrap();
+        graph.increase_flow(b, 2).unwrap();
+        graph.increase_flow(c, 2).unwrap();
+        graph.increase_flow(d, 1).unwrap();
+
+        let decomposition = decompose_flow(&graph, 0, 3).unwrap();
+        assert_eq!(decomposition.paths.len(), 1);
+        let (path, amount) = &decomposition.paths[0];
+        assert_eq!(*amount, 1);
+        assert_eq!(
+            path.nodes().map(|node| node.id()).collect_vec(),
+            vec![0, 1, 3]
+        );
+
+        assert_eq!(decomposition.cycles.len(), 1);
+        let (cycle, amount) = &decomposition.cycles[0];
+        assert_eq!(*amount, 2);
+        assert_eq!(
+            cycle.iter().map(|edge| edge.id()).sorted().collect_vec(),
+            vec![b, c]
+        );
+
+        assert!(matches!(
+            decompose_flow(&graph, 0, 7),
+            Err(AlgoError::SinkNotFound(..))
+        ));
+    }
+}
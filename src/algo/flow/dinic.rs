@@ -71,7 +71,8 @@ where
         }
         while let Some(&(edge_id, node_id)) = self.adj.get_mut(&cur).unwrap().peek() {
             let edge = self.graph.edge(edge_id).unwrap();
-            if self.levels[&node_id] == self.levels[&cur] + 1 && edge.has_residual() {
+            // nodes the level graph didn't reach have no level
+            if self.levels.get(&node_id) == Some(&(self.levels[&cur] + 1)) && edge.has_residual() {
                 let new_pushed = Some(pushed.map_or(edge.residual(), |f| min(f, edge.residual())));
                 if let Some(flow) = self.dfs(node_id, new_pushed) {
                     self.graph
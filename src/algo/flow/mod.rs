@@ -1,2 +1,4 @@
+pub mod circulation;
+pub mod decomposition;
 pub mod dinic;
 pub mod ford_fulkerson;
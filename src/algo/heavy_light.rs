@@ -0,0 +1,445 @@
+use crate::algo::errors::AlgoError;
+use crate::algo::tree::RootedTree;
+use crate::graph::traits::{Graph, UndirectedGraph};
+use crate::graph::types::NodeHashMap;
+
+use ahash::AHashMap;
+use std::ops::Range;
+
+// Splits a rooted tree into chains along heavy edges (to the child with the largest subtree) and
+// lays every chain out at consecutive positions, top to bottom. Any path then crosses O(log n)
+// chains
+pub struct HeavyLightDecomposition<G>
+where
+    G: Graph,
+{
+    ids: Vec<G::NId>,
+    index: NodeHashMap<G, usize>,
+    parent: Vec<Option<usize>>,
+    depth: Vec<usize>,
+    head: Vec<usize>,
+    position: Vec<usize>,
+    // position of the child end of each edge, where edge values are stored
+    edge_position: AHashMap<G::EId, usize>,
+}
+
+// Positions visited by a path within one chain, in decreasing order when reversed
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathRange {
+    pub range: Range<usize>,
+    pub reversed: bool,
+}
+
+impl<G> HeavyLightDecomposition<G>
+where
+    G: UndirectedGraph,
+{
+    pub fn new(graph: &G, root: G::NId) -> Result<Self, AlgoError> {
+        let rooted = RootedTree::new(graph, root)?;
+        let n = rooted.ids.len();
+        let sizes = rooted.sizes();
+
+        let mut depth = vec![0; n];
+        for v in 1..n {
+            depth[v] = depth[rooted.parent[v].unwrap().0] + 1;
+        }
+        let heavy: Vec<Option<usize>> = rooted
+            .children
+            .iter()
+            .map(|children| children.iter().copied().max_by_key(|&c| sizes[c]))
+            .collect();
+
+        // walk each chain down from its head, queueing the light children as new chain heads
+        let mut head = vec![0; n];
+        let mut position = vec![0; n];
+        let mut next_position = 0;
+        let mut heads = vec![0];
+        while let Some(h) = heads.pop() {
+            let mut v = Some(h);
+            while let Some(u) = v {
+                head[u] = h;
+                position[u] = next_position;
+                next_position += 1;
+                heads.extend(rooted.children[u].iter().filter(|&&c| Some(c) != heavy[u]));
+                v = heavy[u];
+            }
+        }
+
+        let edge_position = (1..n)
+            .map(|v| (rooted.parent[v].unwrap().1, position[v]))
+            .collect();
+
+        Ok(HeavyLightDecomposition {
+            parent: rooted.parent.iter().map(|p| p.map(|(u, _)| u)).collect(),
+            ids: rooted.ids,
+            index: rooted.index,
+            depth,
+            head,
+            position,
+            edge_position,
+        })
+    }
+}
+
+impl<G> HeavyLightDecomposition<G>
+where
+    G: Graph,
+{
+    // Number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn position(&self, id: G::NId) -> Option<usize> {
+        Some(self.position[*self.index.get(&id)?])
+    }
+
+    // Position holding the value of an edge, which is the position of its child end
+    pub fn edge_position(&self, id: G::EId) -> Option<usize> {
+        self.edge_position.get(&id).copied()
+    }
+
+    // Topmost node of the chain containing id
+    pub fn head(&self, id: G::NId) -> Option<G::NId> {
+        Some(self.ids[self.head[*self.index.get(&id)?]])
+    }
+
+    // Every chain as node ids from its head downwards, ordered by the position of the head
+    pub fn chains(&self) -> Vec<Vec<G::NId>> {
+        let mut by_position = vec![0; self.len()];
+        for (v, &p) in self.position.iter().enumerate() {
+            by_position[p] = v;
+        }
+
+        let mut chains: Vec<Vec<G::NId>> = Vec::new();
+        for v in by_position {
+            if self.head[v] == v {
+                chains.push(Vec::new());
+            }
+            chains.last_mut().unwrap().push(self.ids[v]);
+        }
+        chains
+    }
+
+    pub fn lca(&self, u: G::NId, v: G::NId) -> Option<G::NId> {
+        let (mut a, mut b) = (*self.index.get(&u)?, *self.index.get(&v)?);
+        while self.head[a] != self.head[b] {
+            if self.depth[self.head[a]] < self.depth[self.head[b]] {
+                std::mem::swap(&mut a, &mut b);
+            }
+            a = self.parent[self.head[a]].unwrap();
+        }
+        match self.depth[a] < self.depth[b] {
+            true => Some(self.ids[a]),
+            false => Some(self.ids[b]),
+        }
+    }
+
+    // Position ranges covering the nodes on the path from u to v, in path order
+    pub fn path_ranges(&self, u: G::NId, v: G::NId) -> Option<Vec<PathRange>> {
+        self.ranges(u, v, false)
+    }
+
+    // Position ranges covering the edges on the path from u to v, in path order. This is the
+    // node ranges without the lowest common ancestor, since edges sit at their child end
+    pub fn edge_path_ranges(&self, u: G::NId, v: G::NId) -> Option<Vec<PathRange>> {
+        self.ranges(u, v, true)
+    }
+
+    fn ranges(&self, u: G::NId, v: G::NId, skip_lca: bool) -> Option<Vec<PathRange>> {
+        let (mut a, mut b) = (*self.index.get(&u)?, *self.index.get(&v)?);
+        // ranges climbing up from u, and ranges descending to v collected bottom up
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+
+        while self.head[a] != self.head[b] {
+            if self.depth[self.head[a]] >= self.depth[self.head[b]] {
+                up.push(self.position[self.head[a]]..self.position[a] + 1);
+                a = self.parent[self.head[a]].unwrap();
+            } else {
+                down.push(self.position[self.head[b]]..self.position[b] + 1);
+                b = self.parent[self.head[b]].unwrap();
+            }
+        }
+        let skip = skip_lca as usize;
+        if self.depth[a] >= self.depth[b] {
+            up.push(self.position[b] + skip..self.position[a] + 1);
+        } else {
+            down.push(self.position[a] + skip..self.position[b] + 1);
+        }
+
+        let up = up.into_iter().map(|range| PathRange {
+            range,
+            reversed: true,
+        });
+        let down = down.into_iter().rev().map(|range| PathRange {
+            range,
+            reversed: false,
+        });
+        Some(up.chain(down).filter(|r| !r.range.is_empty()).collect())
+    }
+}
+
+// Folds values along tree paths with an associative combine that need not be commutative. Values
+// live on nodes or on edges, where a path over edges skips the value at its lowest common ancestor
+pub struct PathQuery<'h, G, T, F>
+where
+    G: Graph,
+    F: Fn(&T, &T) -> T,
+{
+    hld: &'h HeavyLightDecomposition<G>,
+    on_edges: bool,
+    identity: T,
+    combine: F,
+    // bottom up segment tree where every entry holds the fold in position order and its reverse
+    tree: Vec<(T, T)>,
+}
+
+impl<'h, G, T, F> PathQuery<'h, G, T, F>
+where
+    G: Graph,
+    T: Clone,
+    F: Fn(&T, &T) -> T,
+{
+    pub fn over_nodes<V>(
+        hld: &'h HeavyLightDecomposition<G>,
+        identity: T,
+        combine: F,
+        value: V,
+    ) -> Self
+    where
+        V: Fn(G::NId) -> T,
+    {
+        let mut values = vec![identity.clone(); hld.len()];
+        for (v, &id) in hld.ids.iter().enumerate() {
+            values[hld.position[v]] = value(id);
+        }
+        PathQuery::new(hld, false, identity, combine, values)
+    }
+
+    // The root's position holds identity
+    pub fn over_edges<V>(
+        hld: &'h HeavyLightDecomposition<G>,
+        identity: T,
+        combine: F,
+        value: V,
+    ) -> Self
+    where
+        V: Fn(G::EId) -> T,
+    {
+        let mut values = vec![identity.clone(); hld.len()];
+        for (&id, &position) in hld.edge_position.iter() {
+            values[position] = value(id);
+        }
+        PathQuery::new(hld, true, identity, combine, values)
+    }
+
+    fn new(
+        hld: &'h HeavyLightDecomposition<G>,
+        on_edges: bool,
+        identity: T,
+        combine: F,
+        values: Vec<T>,
+    ) -> Self {
+        let n = values.len();
+        let mut tree = vec![(identity.clone(), identity.clone()); 2 * n];
+        for (i, value) in values.into_iter().enumerate() {
+            tree[n + i] = (value.clone(), value);
+        }
+        let mut query = PathQuery {
+            hld,
+            on_edges,
+            identity,
+            combine,
+            tree,
+        };
+        for i in (1..n).rev() {
+            query.pull(i);
+        }
+        query
+    }
+
+    // Fold of the values along the path from u to v, None if either node is not in the tree
+    pub fn query(&self, u: G::NId, v: G::NId) -> Option<T> {
+        let ranges = self.hld.ranges(u, v, self.on_edges)?;
+        Some(ranges.into_iter().fold(self.identity.clone(), |acc, r| {
+            (self.combine)(&acc, &self.fold(r.range, r.reversed))
+        }))
+    }
+
+    // Replaces the value of a node and returns the previous one, None for unknown nodes or when
+    // values live on edges
+    pub fn update_node(&mut self, id: G::NId, value: T) -> Option<T> {
+        if self.on_edges {
+            return None;
+        }
+        let position = self.hld.position(id)?;
+        Some(self.set(position, value))
+    }
+
+    // Replaces the value of an edge and returns the previous one, None for edges outside the tree
+    // or when values live on nodes
+    pub fn update_edge(&mut self, id: G::EId, value: T) -> Option<T> {
+        if !self.on_edges {
+            return None;
+        }
+        let position = self.hld.edge_position(id)?;
+        Some(self.set(position, value))
+    }
+
+    fn set(&mut self, position: usize, value: T) -> T {
+        let mut i = position + self.hld.len();
+        let (old, _) = std::mem::replace(&mut self.tree[i], (value.clone(), value));
+        while i > 1 {
+            i /= 2;
+            self.pull(i);
+        }
+        old
+    }
+
+    fn pull(&mut self, i: usize) {
+        let (left, right) = (&self.tree[2 * i], &self.tree[2 * i + 1]);
+        self.tree[i] = (
+            (self.combine)(&left.0, &right.0),
+            (self.combine)(&right.1, &left.1),
+        );
+    }
+
+    // Fold over a range of positions, taken in decreasing order when reversed
+    fn fold(&self, range: Range<usize>, reversed: bool) -> T {
+        let n = self.hld.len();
+        let (mut l, mut r) = (range.start + n, range.end + n);
+        let mut left = self.identity.clone();
+        let mut right = self.identity.clone();
+        while l < r {
+            if l % 2 == 1 {
+                left = match reversed {
+                    false => (self.combine)(&left, &self.tree[l].0),
+                    true => (self.combine)(&self.tree[l].1, &left),
+                };
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                right = match reversed {
+                    false => (self.combine)(&self.tree[r].0, &right),
+                    true => (self.combine)(&right, &self.tree[r].1),
+                };
+            }
+            l /= 2;
+            r /= 2;
+        }
+        match reversed {
+            false => (self.combine)(&left, &right),
+            true => (self.combine)(&right, &left),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::heavy_light::{HeavyLightDecomposition, PathQuery};
+    use crate::graph::traits::{Graph, OrdinalGraph};
+    use crate::graph::types::UnListGraph;
+    use itertools::Itertools;
+
+    //         0
+    //       / | \
+    //      1  2  3
+    //     /|     |
+    //    4 5     6
+    //   /|       |
+    //  7 8       9
+    fn tree() -> UnListGraph<char, usize> {
+        UnListGraph::from_ordinal(
+            "abcdefghij".chars().collect(),
+            vec![
+                (0, 1, 1),
+                (0, 2, 2),
+                (0, 3, 3),
+                (1, 4, 4),
+                (1, 5, 5),
+                (3, 6, 6),
+                (4, 7, 7),
+                (4, 8, 8),
+                (6, 9, 9),
+            ],
+        )
+    }
+
+    #[test]
+    fn chains_cover_tree() {
+        let graph = tree();
+        let hld = HeavyLightDecomposition::new(&graph, 0).unwrap();
+        let chains = hld.chains();
+
+        assert_eq!(chains[0][..3], [0, 1, 4]);
+        assert_eq!(chains[0].len(), 4);
+        assert_eq!(chains.iter().map(|c| c.len()).sum::<usize>(), 10);
+        let positions = (0..10)
+            .map(|v| hld.position(v).unwrap())
+            .sorted()
+            .collect_vec();
+        assert_eq!(positions, (0..10).collect_vec());
+        for chain in &chains {
+            for (a, b) in chain.iter().tuple_windows() {
+                assert_eq!(hld.position(*a).unwrap() + 1, hld.position(*b).unwrap());
+                assert_eq!(hld.head(*b), Some(chain[0]));
+            }
+        }
+
+        assert_eq!(hld.lca(7, 5), Some(1));
+        assert_eq!(hld.lca(8, 9), Some(0));
+        assert_eq!(hld.lca(6, 9), Some(6));
+        assert_eq!(hld.lca(6, 10), None);
+    }
+
+    #[test]
+    fn non_commutative_path_fold() {
+        let graph = tree();
+        let hld = HeavyLightDecomposition::new(&graph, 0).unwrap();
+        let mut query = PathQuery::over_nodes(
+            &hld,
+            String::new(),
+            |a: &String, b: &String| format!("{}{}", a, b),
+            |id| graph.node(id).unwrap().to_string(),
+        );
+
+        assert_eq!(query.query(8, 9).unwrap(), "iebadgj");
+        assert_eq!(query.query(9, 8).unwrap(), "jgdabei");
+        assert_eq!(query.query(7, 5).unwrap(), "hebf");
+        assert_eq!(query.query(2, 2).unwrap(), "c");
+
+        assert_eq!(query.update_node(1, "B".to_string()), Some("b".to_string()));
+        assert_eq!(query.query(7, 5).unwrap(), "heBf");
+        assert_eq!(query.update_edge(1, "x".to_string()), None);
+    }
+
+    #[test]
+    fn edge_path_sums() {
+        let graph = tree();
+        let hld = HeavyLightDecomposition::new(&graph, 0).unwrap();
+        let mut query = PathQuery::over_edges(
+            &hld,
+            0,
+            |a: &usize, b: &usize| a + b,
+            |id| *graph.edge(id).unwrap().data(),
+        );
+
+        // 8 -> 4 -> 1 -> 0 -> 3 -> 6 -> 9
+        assert_eq!(query.query(8, 9), Some(8 + 4 + 1 + 3 + 6 + 9));
+        assert_eq!(query.query(7, 8), Some(7 + 8));
+        assert_eq!(query.query(5, 5), Some(0));
+
+        let edge = graph.between(3, 6).unwrap().id();
+        assert_eq!(query.update_edge(edge, 100), Some(6));
+        assert_eq!(query.query(9, 0), Some(9 + 100 + 3));
+        // the chain 3 -> 6 -> 9 and then the root, whose own range is empty for edges
+        assert_eq!(hld.path_ranges(9, 0).unwrap().len(), 2);
+        assert_eq!(hld.edge_path_ranges(9, 0).unwrap().len(), 1);
+    }
+}
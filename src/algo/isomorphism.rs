@@ -0,0 +1,514 @@
+use crate::graph::edge::Edge;
+use crate::graph::node::Node;
+use crate::graph::traits::{Graph, GraphIter};
+use crate::graph::types::NodeHashMap;
+
+use dary_heap::DaryHeap;
+use std::cmp::Reverse;
+
+// Edges are matched with `between`, so graphs are expected to have at most one edge per ordered
+// pair of nodes (or per unordered pair for undirected graphs)
+pub fn is_isomorphic<G1, G2>(g1: &G1, g2: &G2) -> bool
+where
+    G1: GraphIter,
+    G2: GraphIter,
+{
+    is_isomorphic_where(g1, g2, |_, _| true, |_, _| true)
+}
+
+pub fn is_isomorphic_where<'a, 'b, G1, G2, NM, EM>(
+    g1: &'a G1,
+    g2: &'b G2,
+    node_match: NM,
+    edge_match: EM,
+) -> bool
+where
+    G1: GraphIter,
+    G2: GraphIter,
+    NM: Fn(&Node<'a, G1::NId, G1::N>, &Node<'b, G2::NId, G2::N>) -> bool,
+    EM: Fn(&Edge<'a, G1::NId, G1::EId, G1::E>, &Edge<'b, G2::NId, G2::EId, G2::E>) -> bool,
+{
+    if g1.len() != g2.len() {
+        return false;
+    }
+    let mut degrees1: Vec<usize> = g1.nodes().map(|node| g1.degree(node.id())).collect();
+    let mut degrees2: Vec<usize> = g2.nodes().map(|node| g2.degree(node.id())).collect();
+    degrees1.sort_unstable();
+    degrees2.sort_unstable();
+    if degrees1 != degrees2 {
+        return false;
+    }
+
+    Vf2::new(g1, g2, node_match, edge_match, true)
+        .next()
+        .is_some()
+}
+
+// Lazily yields every injective map from pattern nodes to target nodes that sends each pattern
+// edge onto a target edge. Target edges between mapped nodes need not exist in the pattern
+pub fn subgraph_monomorphisms<'a, 'b, P, T>(
+    pattern: &'a P,
+    target: &'b T,
+) -> Vf2<'a, 'b, P, T, NodeMatch<'a, 'b, P, T>, EdgeMatch<'a, 'b, P, T>>
+where
+    P: GraphIter,
+    T: GraphIter,
+{
+    Vf2::new(pattern, target, |_, _| true, |_, _| true, false)
+}
+
+pub fn subgraph_monomorphisms_where<'a, 'b, P, T, NM, EM>(
+    pattern: &'a P,
+    target: &'b T,
+    node_match: NM,
+    edge_match: EM,
+) -> Vf2<'a, 'b, P, T, NM, EM>
+where
+    P: GraphIter,
+    T: GraphIter,
+    NM: Fn(&Node<'a, P::NId, P::N>, &Node<'b, T::NId, T::N>) -> bool,
+    EM: Fn(&Edge<'a, P::NId, P::EId, P::E>, &Edge<'b, T::NId, T::EId, T::E>) -> bool,
+{
+    Vf2::new(pattern, target, node_match, edge_match, false)
+}
+
+pub type NodeMatch<'a, 'b, P, T> = fn(
+    &Node<'a, <P as Graph>::NId, <P as Graph>::N>,
+    &Node<'b, <T as Graph>::NId, <T as Graph>::N>,
+) -> bool;
+
+pub type EdgeMatch<'a, 'b, P, T> = fn(
+    &Edge<'a, <P as Graph>::NId, <P as Graph>::EId, <P as Graph>::E>,
+    &Edge<'b, <T as Graph>::NId, <T as Graph>::EId, <T as Graph>::E>,
+) -> bool;
+
+pub struct Vf2<'a, 'b, P, T, NM, EM>
+where
+    P: GraphIter,
+    T: GraphIter,
+    NM: Fn(&Node<'a, P::NId, P::N>, &Node<'b, T::NId, T::N>) -> bool,
+    EM: Fn(&Edge<'a, P::NId, P::EId, P::E>, &Edge<'b, T::NId, T::EId, T::E>) -> bool,
+{
+    pattern: Indexed<'a, P>,
+    target: Indexed<'b, T>,
+    node_match: NM,
+    edge_match: EM,
+    // isomorphism instead of monomorphism: mapped neighborhoods must correspond exactly
+    induced: bool,
+    // pattern nodes in matching order, each with an earlier neighbor to draw candidates from
+    order: Vec<(usize, Option<usize>)>,
+    core_p: Vec<Option<usize>>,
+    core_t: Vec<Option<usize>>,
+    // remaining target candidates for the pattern node at each depth
+    stack: Vec<Vec<usize>>,
+    started: bool,
+}
+
+// Nodes indexed 0..n with edges as (neighbor index, edge id), following edge direction as stored
+struct Indexed<'a, G>
+where
+    G: GraphIter,
+{
+    graph: &'a G,
+    ids: Vec<G::NId>,
+    out: Vec<Vec<(usize, G::EId)>>,
+    inc: Vec<Vec<(usize, G::EId)>>,
+    // distinct neighbors regardless of direction, excluding self
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl<'a, 'b, P, T, NM, EM> Iterator for Vf2<'a, 'b, P, T, NM, EM>
+where
+    P: GraphIter,
+    T: GraphIter,
+    NM: Fn(&Node<'a, P::NId, P::N>, &Node<'b, T::NId, T::N>) -> bool,
+    EM: Fn(&Edge<'a, P::NId, P::EId, P::E>, &Edge<'b, T::NId, T::EId, T::E>) -> bool,
+{
+    type Item = NodeHashMap<P, T::NId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.order.is_empty() {
+                return Some(NodeHashMap::<P, T::NId>::default());
+            }
+            let candidates = self.candidates(0);
+            self.stack.push(candidates);
+        }
+
+        loop {
+            let depth = self.stack.len();
+            if depth == 0 {
+                return None;
+            }
+            let (p, _) = self.order[depth - 1];
+            if let Some(t) = self.core_p[p].take() {
+                self.core_t[t] = None;
+            }
+
+            let t = match self.stack.last_mut().unwrap().pop() {
+                Some(t) => t,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            if !self.feasible(p, t) {
+                continue;
+            }
+            self.core_p[p] = Some(t);
+            self.core_t[t] = Some(p);
+
+            if depth == self.order.len() {
+                return Some(
+                    self.core_p
+                        .iter()
+                        .enumerate()
+                        .map(|(p, t)| (self.pattern.ids[p], self.target.ids[t.unwrap()]))
+                        .collect(),
+                );
+            }
+            let candidates = self.candidates(depth);
+            self.stack.push(candidates);
+        }
+    }
+}
+
+impl<'a, 'b, P, T, NM, EM> Vf2<'a, 'b, P, T, NM, EM>
+where
+    P: GraphIter,
+    T: GraphIter,
+    NM: Fn(&Node<'a, P::NId, P::N>, &Node<'b, T::NId, T::N>) -> bool,
+    EM: Fn(&Edge<'a, P::NId, P::EId, P::E>, &Edge<'b, T::NId, T::EId, T::E>) -> bool,
+{
+    fn new(pattern: &'a P, target: &'b T, node_match: NM, edge_match: EM, induced: bool) -> Self {
+        let pattern = Indexed::new(pattern);
+        let target = Indexed::new(target);
+        let order = matching_order(&pattern.neighbors);
+        let (np, nt) = (pattern.ids.len(), target.ids.len());
+
+        Vf2 {
+            pattern,
+            target,
+            node_match,
+            edge_match,
+            induced,
+            order,
+            core_p: vec![None; np],
+            core_t: vec![None; nt],
+            stack: Vec::new(),
+            // a pattern larger than the target has no matches, so behave as already exhausted
+            started: np > nt,
+        }
+    }
+
+    fn candidates(&self, depth: usize) -> Vec<usize> {
+        let (_, parent) = self.order[depth];
+        let pool: Vec<usize> = match parent {
+            Some(q) => self.target.neighbors[self.core_p[q].unwrap()].clone(),
+            None => (0..self.target.ids.len()).collect(),
+        };
+        let mut candidates: Vec<usize> = pool
+            .into_iter()
+            .filter(|&t| self.core_t[t].is_none())
+            .collect();
+        // popped from the back, so reverse to try candidates in index order
+        candidates.reverse();
+        candidates
+    }
+
+    fn feasible(&self, p: usize, t: usize) -> bool {
+        let (pattern, target) = (&self.pattern, &self.target);
+        let p_degree = pattern.out[p].len() + pattern.inc[p].len();
+        let t_degree = target.out[t].len() + target.inc[t].len();
+        if (self.induced && p_degree != t_degree) || p_degree > t_degree {
+            return false;
+        }
+
+        if self.induced {
+            let mapped = |nbrs: &Vec<usize>, core: &Vec<Option<usize>>| {
+                nbrs.iter().filter(|&&x| core[x].is_some()).count()
+            };
+            if mapped(&pattern.neighbors[p], &self.core_p)
+                != mapped(&target.neighbors[t], &self.core_t)
+            {
+                return false;
+            }
+        }
+
+        let p_node = pattern.graph.node(pattern.ids[p]).unwrap();
+        let t_node = target.graph.node(target.ids[t]).unwrap();
+        if !(self.node_match)(&p_node, &t_node) {
+            return false;
+        }
+
+        // every pattern edge to an already mapped node (or a self loop) must have an image
+        let image = |q: usize| match q == p {
+            true => Some(t),
+            false => self.core_p[q],
+        };
+        for &(q, edge_id) in &pattern.out[p] {
+            if let Some(tq) = image(q) {
+                if !self.edge_matches(edge_id, t, tq) {
+                    return false;
+                }
+            }
+        }
+        for &(q, edge_id) in &pattern.inc[p] {
+            if q == p {
+                continue;
+            }
+            if let Some(tq) = image(q) {
+                if !self.edge_matches(edge_id, tq, t) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn edge_matches(&self, pattern_edge: P::EId, tu: usize, tv: usize) -> bool {
+        let target = &self.target;
+        match target.graph.between(target.ids[tu], target.ids[tv]) {
+            Some(t_edge) => {
+                let p_edge = self.pattern.graph.edge(pattern_edge).unwrap();
+                (self.edge_match)(&p_edge, &t_edge)
+            }
+            None => false,
+        }
+    }
+}
+
+impl<'a, G> Indexed<'a, G>
+where
+    G: GraphIter,
+{
+    fn new(graph: &'a G) -> Self {
+        let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+        let mut index = NodeHashMap::<G, usize>::default();
+        for (i, &id) in ids.iter().enumerate() {
+            index.insert(id, i);
+        }
+
+        let mut out = vec![Vec::new(); ids.len()];
+        let mut inc = vec![Vec::new(); ids.len()];
+        let mut neighbors = vec![Vec::new(); ids.len()];
+        for edge in graph.edges() {
+            let (u, v) = (index[&edge.u()], index[&edge.v()]);
+            out[u].push((v, edge.id()));
+            inc[v].push((u, edge.id()));
+            if u != v {
+                neighbors[u].push(v);
+                neighbors[v].push(u);
+            }
+        }
+        for list in neighbors.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        Indexed {
+            graph,
+            ids,
+            out,
+            inc,
+            neighbors,
+        }
+    }
+}
+
+// VF2++ style ordering: grow the order through the pattern's connectivity, always taking the node
+// with the most already ordered neighbors and breaking ties by degree. Each node after the first
+// of its component records an ordered neighbor whose image bounds its candidates
+fn matching_order(neighbors: &[Vec<usize>]) -> Vec<(usize, Option<usize>)> {
+    let n = neighbors.len();
+    let mut ordered = vec![false; n];
+    let mut connections = vec![0; n];
+    let mut parent = vec![None; n];
+    let mut order = Vec::with_capacity(n);
+
+    let mut roots: Vec<usize> = (0..n).collect();
+    roots.sort_by_key(|&v| Reverse(neighbors[v].len()));
+
+    for root in roots {
+        if ordered[root] {
+            continue;
+        }
+        let mut heap: DaryHeap<(usize, usize, Reverse<usize>), 4> = DaryHeap::new();
+        heap.push((0, neighbors[root].len(), Reverse(root)));
+        while let Some((conn, _, Reverse(v))) = heap.pop() {
+            if ordered[v] || conn != connections[v] {
+                continue;
+            }
+            ordered[v] = true;
+            order.push((v, parent[v]));
+            for &w in &neighbors[v] {
+                if !ordered[w] {
+                    connections[w] += 1;
+                    parent[w].get_or_insert(v);
+                    heap.push((connections[w], neighbors[w].len(), Reverse(w)));
+                }
+            }
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::isomorphism::{
+        is_isomorphic, is_isomorphic_where, subgraph_monomorphisms, subgraph_monomorphisms_where,
+    };
+    use crate::graph::traits::{GraphMut, KeyedGraph, OrdinalGraph};
+    use crate::graph::types::{DiListGraph, DiMapGraph, UnListGraph};
+    use itertools::Itertools;
+
+    fn cycle(n: usize) -> UnListGraph<(), ()> {
+        UnListGraph::from_ordinal(vec![(); n], (0..n).map(|i| (i, (i + 1) % n, ())).collect())
+    }
+
+    #[test]
+    fn relabelled_cycles_are_isomorphic() {
+        let a = cycle(6);
+        let b = UnListGraph::builder()
+            .with_size(6)
+            .edge(0, 3, ())
+            .edge(3, 1, ())
+            .edge(1, 4, ())
+            .edge(4, 2, ())
+            .edge(2, 5, ())
+            .edge(5, 0, ())
+            .build();
+        assert!(is_isomorphic(&a, &b));
+
+        // two triangles have the same degree sequence as a 6-cycle
+        let triangles = UnListGraph::builder()
+            .with_size(6)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 0, ())
+            .edge(3, 4, ())
+            .edge(4, 5, ())
+            .edge(5, 3, ())
+            .build();
+        assert!(!is_isomorphic(&a, &triangles));
+    }
+
+    #[test]
+    fn direction_matters() {
+        // 0 -> 1 -> 2 vs 0 -> 1 <- 2
+        let path = DiListGraph::builder()
+            .with_size(3)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .build();
+        let collider = DiListGraph::builder()
+            .with_size(3)
+            .edge(0, 1, ())
+            .edge(2, 1, ())
+            .build();
+        let reversed = DiListGraph::builder()
+            .with_size(3)
+            .edge(2, 1, ())
+            .edge(1, 0, ())
+            .build();
+        assert!(!is_isomorphic(&path, &collider));
+        assert!(is_isomorphic(&path, &reversed));
+    }
+
+    #[test]
+    fn labelled_isomorphism() {
+        let a = UnListGraph::from_ordinal(vec!['x', 'y', 'y'], vec![(0, 1, 1), (1, 2, 2)]);
+        let b = UnListGraph::from_ordinal(vec!['y', 'x', 'y'], vec![(1, 0, 1), (0, 2, 2)]);
+        let c = UnListGraph::from_ordinal(vec!['y', 'x', 'y'], vec![(1, 0, 2), (0, 2, 1)]);
+        assert!(is_isomorphic_where(
+            &a,
+            &b,
+            |u, v| **u == **v,
+            |e, f| **e == **f
+        ));
+        assert!(!is_isomorphic_where(
+            &a,
+            &c,
+            |u, v| **u == **v,
+            |e, f| **e == **f
+        ));
+        assert!(is_isomorphic_where(&a, &c, |u, v| **u == **v, |_, _| true));
+    }
+
+    #[test]
+    fn triangles_in_square_with_diagonal() {
+        // 0 --- 1
+        // |   / |
+        // |  /  |
+        // 3 --- 2
+        let target = UnListGraph::builder()
+            .with_size(4)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(2, 3, ())
+            .edge(3, 0, ())
+            .edge(1, 3, ())
+            .build();
+        let triangle = cycle(3);
+
+        // two triangles, each matched in 3! ways
+        let matches = subgraph_monomorphisms(&triangle, &target).collect_vec();
+        assert_eq!(matches.len(), 12);
+        for m in &matches {
+            let image = m.values().copied().sorted().collect_vec();
+            assert!(image == vec![0, 1, 3] || image == vec![1, 2, 3]);
+        }
+
+        // a 4-cycle is a subgraph (monomorphism) even though the target has an extra chord
+        assert_eq!(subgraph_monomorphisms(&cycle(4), &target).count(), 8);
+    }
+
+    #[test]
+    fn netlist_pattern_search() {
+        // find inverter -> nand chains in a small netlist
+        let mut netlist = DiMapGraph::new();
+        netlist.put_node("a", "inv");
+        netlist.put_node("b", "nand");
+        netlist.put_node("c", "inv");
+        netlist.put_node("d", "nand");
+        netlist.put_node("e", "nor");
+        netlist
+            .insert_edge("a", "b", ())
+            .expect("nodes should exist");
+        netlist
+            .insert_edge("c", "b", ())
+            .expect("nodes should exist");
+        netlist
+            .insert_edge("c", "d", ())
+            .expect("nodes should exist");
+        netlist
+            .insert_edge("b", "e", ())
+            .expect("nodes should exist");
+        netlist
+            .insert_edge("d", "c", ())
+            .expect("nodes should exist");
+
+        let pattern = DiListGraph::from_ordinal(vec!["inv", "nand"], vec![(0, 1, ())]);
+        let found = subgraph_monomorphisms_where(
+            &pattern,
+            &netlist,
+            |p, t| *p.data() == *t.data(),
+            |_, _| true,
+        )
+        .map(|m| (m[&0], m[&1]))
+        .sorted()
+        .collect_vec();
+        assert_eq!(found, vec![("a", "b"), ("c", "b"), ("c", "d")]);
+    }
+
+    #[test]
+    fn pattern_larger_than_target() {
+        let target = cycle(3);
+        assert_eq!(subgraph_monomorphisms(&cycle(4), &target).count(), 0);
+
+        let empty = UnListGraph::<(), ()>::new();
+        assert_eq!(subgraph_monomorphisms(&empty, &target).count(), 1);
+    }
+}
@@ -2,20 +2,25 @@ use ahash::AHashSet;
 use dary_heap::DaryHeap;
 use itertools::Itertools;
 
+use crate::algo::flow::dinic::dinic;
 use crate::graph::edge::Edge;
-
-use crate::graph::traits::{GraphIter, UndirectedGraph};
-use crate::graph::types::NodeHashMap;
+use crate::graph::edge_traits::{Capacity, Weighted};
+use crate::graph::flow::{FlowGraph, FlowValue};
+use crate::graph::traits::{GraphIter, KeyedGraph, OrdinalGraph, UndirectedGraph, WithCapacity};
+use crate::graph::types::{FlowFlatGraph, NodeHashMap};
+use crate::iter::bfs::bfs_where;
 use crate::utils::disjoint_sets::DisjointSet;
 
+use std::marker::PhantomData;
 use std::ops::{Add, Sub};
 use std::rc::Rc;
 
 // Stoer Wagner
-pub fn mincut<'a, G>(graph: &'a G) -> Quotient<'a, G>
+pub fn mincut<'a, G, W>(graph: &'a G) -> Quotient<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
     let mut q = Quotient::new(graph);
     let mut best = q.clone();
@@ -29,15 +34,16 @@ where
     best
 }
 
-fn phase<'a, G>(mut quotient: Quotient<'a, G>) -> (Quotient<'a, G>, usize, usize)
+fn phase<'a, G, W>(mut quotient: Quotient<'a, G, W>) -> (Quotient<'a, G, W>, usize, usize)
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
     let start = quotient.graph().nodes().next().unwrap().id();
     let start_id = quotient.subset(start);
-    let mut heap: DaryHeap<(G::E, usize), 4> = DaryHeap::new();
-    let mut weight = vec![G::E::default(); quotient.graph().n()];
+    let mut heap: DaryHeap<(W, usize), 4> = DaryHeap::new();
+    let mut weight = vec![W::default(); quotient.graph().n()];
     for edge in quotient.adj(start_id) {
         let other = edge.other(start_id);
         weight[other] = weight[other].clone() + edge.data().clone();
@@ -78,23 +84,26 @@ where
     (quotient, s, t)
 }
 
-pub struct Partition<'a, G>
+pub struct Partition<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
     graph: &'a G,
     ids: Rc<NodeHashMap<G, usize>>,
     ds: DisjointSet,
     components: usize,
+    weight: PhantomData<W>,
 }
 
-impl<'a, G> Partition<'a, G>
+impl<'a, G, W> Partition<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
-    pub fn new(g: &'a G) -> Partition<'a, G> {
+    pub fn new(g: &'a G) -> Partition<'a, G, W> {
         let mut ids = NodeHashMap::<G, usize>::default();
         let mut counter = 0;
         for node in g.nodes() {
@@ -108,6 +117,7 @@ where
             ids: ids.into(),
             ds: DisjointSet::with_len(n),
             components: n,
+            weight: PhantomData,
         }
     }
 
@@ -143,10 +153,10 @@ where
         self.ds.root(id)
     }
 
-    pub fn cut_weight(&self) -> G::E {
+    pub fn cut_weight(&self) -> W {
         self.cut_edges()
             .into_iter()
-            .fold(G::E::default(), |acc, e| acc + e.data().clone())
+            .fold(W::default(), |acc, e| acc + e.data().weight())
     }
 
     pub fn cut_edges(&self) -> Vec<Edge<'a, G::NId, G::EId, G::E>> {
@@ -165,10 +175,11 @@ where
     }
 }
 
-impl<'a, G> Clone for Partition<'a, G>
+impl<'a, G, W> Clone for Partition<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
     fn clone(&self) -> Self {
         Self {
@@ -176,25 +187,28 @@ where
             ids: self.ids.clone(),
             ds: self.ds.clone(),
             components: self.components,
+            weight: PhantomData,
         }
     }
 }
 
-pub struct Quotient<'a, G>
+pub struct Quotient<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
-    partition: Partition<'a, G>,
+    partition: Partition<'a, G, W>,
     edges: Vec<AHashSet<G::EId>>,
 }
 
-impl<'a, G> Quotient<'a, G>
+impl<'a, G, W> Quotient<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
-    pub fn new(g: &'a G) -> Quotient<'a, G> {
+    pub fn new(g: &'a G) -> Quotient<'a, G, W> {
         let partition = Partition::new(g);
         let mut edges = vec![AHashSet::new(); g.len().0];
         for edge in g.edges() {
@@ -258,7 +272,7 @@ where
         self.partition.root(id)
     }
 
-    pub fn cut_weight(&self) -> G::E {
+    pub fn cut_weight(&self) -> W {
         self.partition.cut_weight()
     }
 
@@ -266,7 +280,7 @@ where
         self.partition.cut_edges()
     }
 
-    pub fn adj(&self, id: usize) -> Vec<Edge<'a, usize, G::EId, G::E>> {
+    pub fn adj(&self, id: usize) -> Vec<Edge<'a, usize, G::EId, W>> {
         let root = self.root(id);
         self.edges[root]
             .iter()
@@ -276,7 +290,7 @@ where
                     edge.id(),
                     self.subset(edge.u()),
                     self.subset(edge.v()),
-                    edge.data().clone(),
+                    edge.data().weight(),
                 )
             })
             .collect_vec()
@@ -291,10 +305,11 @@ where
     }
 }
 
-impl<'a, G> Clone for Quotient<'a, G>
+impl<'a, G, W> Clone for Quotient<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Default + Add<Output = G::E> + Sub<Output = G::E> + Ord + Clone,
+    G::E: Weighted<W>,
+    W: Default + Add<Output = W> + Sub<Output = W> + Ord + Clone,
 {
     fn clone(&self) -> Self {
         Self {
@@ -304,9 +319,157 @@ where
     }
 }
 
+// Gusfield's variant of Gomory-Hu: n - 1 max flow computations on the original graph, no
+// contractions. The resulting tree has the same minimum s-t cut value as the graph for every pair
+// of nodes, which is the smallest edge weight on the tree path between them
+pub fn gomory_hu<G, C>(graph: &G) -> GomoryHuTree<G, C>
+where
+    G: UndirectedGraph + GraphIter,
+    G::E: Capacity<C>,
+    C: FlowValue,
+{
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+    let n = ids.len();
+
+    // every undirected edge becomes a flow edge in each direction
+    let mut flow_graph = FlowFlatGraph::<(), C>::with_capacity(n, 4 * graph.m());
+    for _ in 0..n {
+        flow_graph.insert_node(());
+    }
+    for edge in graph.edges() {
+        let (u, v) = (index[&edge.u()], index[&edge.v()]);
+        if u != v {
+            let capacity = edge.data().capacity();
+            flow_graph
+                .insert_flow_edge(u, v, capacity)
+                .expect("node ids should exist");
+            flow_graph
+                .insert_flow_edge(v, u, capacity)
+                .expect("node ids should exist");
+        }
+    }
+
+    let mut parent = vec![0; n];
+    let mut weight = vec![C::default(); n];
+    for s in 1..n {
+        let t = parent[s];
+        flow_graph.reset_flow();
+        let cut = dinic(&mut flow_graph, s, t).expect("nodes should exist");
+
+        let mut source_side = vec![false; n];
+        for (_, node) in bfs_where(&flow_graph, s, |edge, _| edge.has_residual()) {
+            source_side[node.id()] = true;
+        }
+
+        for i in 0..n {
+            if i != s && source_side[i] && parent[i] == t {
+                parent[i] = s;
+            }
+        }
+        weight[s] = cut;
+        // keep the tree a cut tree when t's parent ends up on s's side of the cut
+        if t != 0 && source_side[parent[t]] {
+            parent[s] = parent[t];
+            parent[t] = s;
+            weight[s] = weight[t];
+            weight[t] = cut;
+        }
+    }
+
+    let mut depth = vec![None; n];
+    if n > 0 {
+        depth[0] = Some(0);
+    }
+    for v in 0..n {
+        tree_depth(v, &parent, &mut depth);
+    }
+
+    GomoryHuTree {
+        ids,
+        index,
+        parent,
+        weight,
+        depth: depth.into_iter().map(Option::unwrap).collect(),
+    }
+}
+
+fn tree_depth(v: usize, parent: &[usize], depth: &mut [Option<usize>]) {
+    let mut chain = Vec::new();
+    let mut u = v;
+    while depth[u].is_none() {
+        chain.push(u);
+        u = parent[u];
+    }
+    let mut d = depth[u].unwrap();
+    for &w in chain.iter().rev() {
+        d += 1;
+        depth[w] = Some(d);
+    }
+}
+
+// Rooted at the first node of the graph, where every other node has a parent and the weight of the
+// tree edge to it
+pub struct GomoryHuTree<G, C>
+where
+    G: GraphIter,
+    G::E: Capacity<C>,
+    C: FlowValue,
+{
+    ids: Vec<G::NId>,
+    index: NodeHashMap<G, usize>,
+    parent: Vec<usize>,
+    weight: Vec<C>,
+    depth: Vec<usize>,
+}
+
+impl<G, C> GomoryHuTree<G, C>
+where
+    G: GraphIter,
+    G::E: Capacity<C>,
+    C: FlowValue,
+{
+    // Value of the minimum cut separating u and v, None if either node is not in the graph or if
+    // both are the same node
+    pub fn min_cut_value(&self, u: G::NId, v: G::NId) -> Option<C> {
+        let (mut a, mut b) = (*self.index.get(&u)?, *self.index.get(&v)?);
+        let mut min: Option<C> = None;
+        while a != b {
+            if self.depth[a] < self.depth[b] {
+                std::mem::swap(&mut a, &mut b);
+            }
+            min = Some(min.map_or(self.weight[a], |m| m.min(self.weight[a])));
+            a = self.parent[a];
+        }
+        min
+    }
+
+    // Tree edges as (child, parent, cut value)
+    pub fn edges(&self) -> impl Iterator<Item = (G::NId, G::NId, C)> + '_ {
+        (1..self.ids.len()).map(|v| (self.ids[v], self.ids[self.parent[v]], self.weight[v]))
+    }
+
+    pub fn to_graph<H>(&self) -> H
+    where
+        H: KeyedGraph<NId = G::NId, N = (), E = C> + WithCapacity,
+    {
+        H::from_keyed(
+            self.ids.iter().map(|&id| (id, ())).collect(),
+            self.edges().collect(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{algo::min_cut::mincut, graph::builder::GraphBuilder};
+    use crate::algo::min_cut::{gomory_hu, mincut};
+    use crate::graph::builder::GraphBuilder;
+    use crate::graph::traits::{GraphIter, OrdinalGraph};
+    use crate::graph::types::{UnListGraph, UnMapGraph};
+    use itertools::Itertools;
 
     #[test]
     fn basic_case() {
@@ -327,4 +490,57 @@ mod tests {
         assert_eq!(contraction.cut_weight(), 2);
         assert_eq!(contraction.components(), 2);
     }
+
+    // brute force minimum s-t cut over all bipartitions
+    fn min_cut_brute_force(n: usize, edges: &[(usize, usize, i32)], s: usize, t: usize) -> i32 {
+        (0..1u32 << n)
+            .filter(|mask| mask >> s & 1 == 1 && mask >> t & 1 == 0)
+            .map(|mask| {
+                edges
+                    .iter()
+                    .filter(|(u, v, _)| (mask >> u & 1) != (mask >> v & 1))
+                    .map(|(_, _, c)| c)
+                    .sum()
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn gomory_hu_matches_brute_force() {
+        let edges = vec![
+            (0, 1, 1),
+            (0, 2, 7),
+            (1, 2, 1),
+            (1, 3, 3),
+            (1, 4, 2),
+            (2, 4, 4),
+            (3, 4, 1),
+            (3, 5, 6),
+            (4, 5, 2),
+        ];
+        let graph = UnListGraph::from_ordinal(vec![(); 6], edges.clone());
+        let tree = gomory_hu(&graph);
+        assert_eq!(tree.edges().count(), 5);
+
+        for (s, t) in (0..6).tuple_combinations() {
+            let expected = min_cut_brute_force(6, &edges, s, t);
+            assert_eq!(tree.min_cut_value(s, t), Some(expected));
+            assert_eq!(tree.min_cut_value(t, s), Some(expected));
+        }
+        assert_eq!(tree.min_cut_value(2, 2), None);
+        assert_eq!(tree.min_cut_value(2, 6), None);
+
+        let tree_graph: UnMapGraph<usize, (), i32> = tree.to_graph();
+        assert_eq!(tree_graph.len(), (6, 5));
+    }
+
+    #[test]
+    fn gomory_hu_disconnected() {
+        let graph = UnListGraph::from_ordinal(vec![(); 4], vec![(0, 1, 5), (2, 3, 2)]);
+        let tree = gomory_hu(&graph);
+        assert_eq!(tree.min_cut_value(0, 1), Some(5));
+        assert_eq!(tree.min_cut_value(3, 2), Some(2));
+        assert_eq!(tree.min_cut_value(0, 3), Some(0));
+    }
 }
@@ -1,8 +1,16 @@
 pub mod bridges;
+pub mod cliques;
+pub mod coloring;
 pub mod components;
+pub mod connectivity;
+pub mod cycles;
 pub mod errors;
 pub mod flow;
+pub mod heavy_light;
+pub mod isomorphism;
 pub mod mst;
 pub mod shortest_paths;
+pub mod structure;
 pub mod topological_sort;
+pub mod tree;
 pub mod min_cut;
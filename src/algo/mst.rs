@@ -1,4 +1,5 @@
 use crate::graph::edge::Edge;
+use crate::graph::edge_traits::Weighted;
 use crate::graph::traits::{GraphIter, UndirectedGraph};
 use crate::iter::pfs::{pfs, PriorityType};
 use crate::iter::traits::{Path, PathTree, Tree};
@@ -10,13 +11,14 @@ use std::ops::Add;
 
 // Prim's algorithm for minimum spanning trees
 // (technically minimum spanning forest as we run Prim's for each connected component)
-pub fn mst<'a, G>(graph: &'a G) -> MST<'a, G>
+pub fn mst<'a, G, W>(graph: &'a G) -> MST<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
     let mut tree = PathTree::new(graph);
-    let mut weight = G::E::default();
+    let mut weight = W::default();
     let mut connected_components = 0;
 
     for root in graph.nodes() {
@@ -27,9 +29,9 @@ where
             for (edge, node, edge_weight) in pfs(
                 graph,
                 root_id,
-                G::E::default(),
+                W::default(),
                 PriorityType::Min,
-                |_, edge, _| edge.data().clone(),
+                |_, edge, _| edge.data().weight(),
             ) {
                 weight = weight + edge_weight;
                 tree.insert_parent(node.id(), edge.map(|e| e.id()));
@@ -44,20 +46,22 @@ where
     }
 }
 
-pub struct MST<'a, G>
+pub struct MST<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
     tree: PathTree<'a, G>,
-    weight: G::E,
+    weight: W,
     connected_components: usize,
 }
 
-impl<'a, G> Tree<'a, G> for MST<'a, G>
+impl<'a, G, W> Tree<'a, G> for MST<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
     fn parent_edge(&self, id: G::NId) -> Option<Edge<'a, G::NId, G::EId, G::E>> {
         self.tree.parent_edge(id)
@@ -67,12 +71,13 @@ where
     }
 }
 
-impl<'a, G> MST<'a, G>
+impl<'a, G, W> MST<'a, G, W>
 where
     G: UndirectedGraph + GraphIter,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
-    pub fn weight(&self) -> G::E {
+    pub fn weight(&self) -> W {
         self.weight.clone()
     }
 
@@ -84,8 +89,10 @@ where
 #[cfg(test)]
 mod tests {
     use crate::algo::mst::mst;
+    use crate::graph::edge_traits::Weighted;
     use crate::graph::traits::{GraphMut, KeyedGraph, WithCapacity};
     use crate::graph::types::UnMapGraph;
+    use crate::iter::traits::Tree;
 
     #[test]
     fn mst_base_case() {
@@ -136,4 +143,42 @@ mod tests {
         assert_eq!(tree.weight(), 11);
         assert_eq!(tree.connected_components(), 2);
     }
+
+    struct Cable {
+        cost: i64,
+    }
+
+    impl Weighted<i64> for Cable {
+        fn weight(&self) -> i64 {
+            self.cost
+        }
+
+        fn set_weight(&mut self, val: i64) {
+            self.cost = val;
+        }
+    }
+
+    #[test]
+    fn mst_weighted_edge_data() {
+        let mut graph = UnMapGraph::with_capacity(3, 3);
+        graph.put_node("A", ());
+        graph.put_node("B", ());
+        graph.put_node("C", ());
+        graph
+            .insert_edge("A", "B", Cable { cost: 9 })
+            .expect("nodes should exist");
+        graph
+            .insert_edge("B", "C", Cable { cost: 2 })
+            .expect("nodes should exist");
+        graph
+            .insert_edge("A", "C", Cable { cost: 3 })
+            .expect("nodes should exist");
+
+        let tree = mst(&graph);
+        assert_eq!(tree.weight(), 5);
+        assert!(["A", "B", "C"]
+            .into_iter()
+            .filter_map(|id| tree.parent_edge(id))
+            .all(|edge| edge.cost < 9));
+    }
 }
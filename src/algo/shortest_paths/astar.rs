@@ -1,5 +1,6 @@
 use crate::algo::errors::AlgoError;
 use crate::graph::edge::Edge;
+use crate::graph::edge_traits::Weighted;
 use crate::graph::node::Node;
 use crate::graph::traits::Graph;
 use crate::iter::pfs::{pfs, Pfs, PriorityType};
@@ -8,22 +9,23 @@ use std::cmp::Ordering;
 use std::default::Default;
 use std::ops::Add;
 
-pub fn astar<'a, G>(
+pub fn astar<'a, G, W>(
     graph: &'a G,
     start: G::NId,
-    heuristic: impl Fn(&Node<'a, G::NId, G::N>) -> G::E,
+    heuristic: impl Fn(&Node<'a, G::NId, G::N>) -> W,
 ) -> Result<
     Pfs<
         G,
-        Weight<G::E>,
-        impl Fn(Weight<G::E>, &Edge<'a, G::NId, G::EId, G::E>, &Node<'a, G::NId, G::N>) -> Weight<G::E>,
+        Weight<W>,
+        impl Fn(Weight<W>, &Edge<'a, G::NId, G::EId, G::E>, &Node<'a, G::NId, G::N>) -> Weight<W>,
         impl Fn(&Edge<'a, G::NId, G::EId, G::E>, &Node<'a, G::NId, G::N>) -> bool,
     >,
     AlgoError,
 >
 where
     G: Graph,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
     if !graph.contains_node(start) {
         return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
@@ -33,13 +35,13 @@ where
         graph,
         start,
         Weight {
-            distance: G::E::default(),
-            priority: G::E::default(),
+            distance: W::default(),
+            priority: W::default(),
         },
         PriorityType::Min,
-        move |acc: Weight<G::E>, edge, node| {
-            let distance = acc.distance.clone() + edge.data().clone();
-            let priority = acc.distance + edge.data().clone() + heuristic(node);
+        move |acc: Weight<W>, edge, node| {
+            let distance = acc.distance + edge.data().weight();
+            let priority = distance.clone() + heuristic(node);
             Weight { distance, priority }
         },
     ))
@@ -1,5 +1,6 @@
+use crate::algo::cycles::Cycle;
 use crate::algo::errors::AlgoError;
-use crate::graph::edge::Edge;
+use crate::graph::edge_traits::Weighted;
 use crate::graph::traits::{Graph, GraphIter};
 use crate::iter::traits::{Tree, WeightedPathTree};
 use std::cmp::Ord;
@@ -7,16 +8,15 @@ use std::cmp::Ord;
 use std::default::Default;
 use std::ops::Add;
 
-type Cycle<'a, G> = Vec<Edge<'a, <G as Graph>::NId, <G as Graph>::EId, <G as Graph>::E>>;
-
 // returns shortest path tree and boolean that is true if negative cycle is found
-pub fn bellman_ford<'a, G>(
+pub fn bellman_ford<'a, G, W>(
     graph: &'a G,
     start: G::NId,
-) -> Result<(WeightedPathTree<'a, G, G::E>, Option<Cycle<'a, G>>), AlgoError>
+) -> Result<(WeightedPathTree<'a, G, W>, Option<Cycle<'a, G>>), AlgoError>
 where
     G: Graph + GraphIter,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
     let mut tree = WeightedPathTree::new(graph);
 
@@ -24,7 +24,7 @@ where
         return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
     }
 
-    tree.insert_node(start, None, G::E::default());
+    tree.insert_node(start, None, W::default());
 
     // n-1 iterations to find shortest paths, +1 final iteration to check for negative cycle
     let (iterations, _) = graph.len();
@@ -42,7 +42,7 @@ where
             }
             for (edge, v) in graph.adj(u.id()).unwrap() {
                 let v_id = v.id();
-                let weight = edge.data().clone();
+                let weight = edge.data().weight();
 
                 let u_dist = tree.weight(u.id()).unwrap().clone();
                 let v_dist_new = u_dist + weight;
@@ -77,7 +77,7 @@ where
 
             while let Some(edge) = tree.parent_edge(node_id) {
                 node_id = edge.other(node_id);
-                edges.push(edge.clone());
+                edges.push(edge);
 
                 if node_id == cycle_root_id {
                     break;
@@ -0,0 +1,504 @@
+use crate::algo::errors::AlgoError;
+use crate::graph::edge_traits::Weighted;
+use crate::graph::traits::{DirectedGraph, GraphIter};
+use crate::graph::types::NodeHashMap;
+use crate::iter::traits::Path;
+
+use ahash::AHashMap;
+use dary_heap::DaryHeap;
+use std::cmp::Reverse;
+use std::ops::Add;
+
+// Witness searches give up after settling this many nodes and add the shortcut, which is never
+// wrong, just sometimes unnecessary
+const WITNESS_SETTLE_LIMIT: usize = 128;
+
+// Preprocesses a directed graph whose weights don't change into a contraction hierarchy. Nodes get
+// contracted one by one, cheapest first by edge difference (shortcuts added minus edges removed)
+// plus the number of neighbours contracted before them. Contracting a node adds a shortcut between
+// two of its remaining neighbours whenever the path through it is the only shortest one, so
+// afterwards every shortest path can be found going up the order from both ends
+pub fn contraction_hierarchy<'a, G, W>(graph: &'a G) -> ContractionHierarchy<'a, G, W>
+where
+    G: DirectedGraph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+    let mut contraction = Contraction {
+        arcs: Vec::new(),
+        out: vec![Vec::new(); ids.len()],
+        inc: vec![Vec::new(); ids.len()],
+        contracted: vec![false; ids.len()],
+    };
+    for edge in graph.edges() {
+        let (u, v) = (index[&edge.u()], index[&edge.v()]);
+        if u != v {
+            contraction.add_arc(u, v, edge.data().weight(), Via::Edge(edge.id()));
+        }
+    }
+
+    // lazily updated: a popped node whose priority went up since is pushed back
+    let mut contracted_neighbours = vec![0; ids.len()];
+    let mut queue: DaryHeap<Reverse<(isize, usize)>, 4> = (0..ids.len())
+        .map(|v| Reverse((contraction.edge_difference(v, &contraction.shortcuts(v)), v)))
+        .collect();
+    let mut rank = vec![0; ids.len()];
+    let mut next_rank = 0;
+    while let Some(Reverse((priority, v))) = queue.pop() {
+        let shortcuts = contraction.shortcuts(v);
+        let current = contraction.edge_difference(v, &shortcuts) + contracted_neighbours[v];
+        if queue
+            .peek()
+            .is_some_and(|Reverse((next, _))| current > *next && current > priority)
+        {
+            queue.push(Reverse((current, v)));
+            continue;
+        }
+        for (a, b) in shortcuts {
+            let (u, w) = (contraction.arcs[a].from, contraction.arcs[b].to);
+            let weight = contraction.arcs[a].weight.clone() + contraction.arcs[b].weight.clone();
+            contraction.add_arc(u, w, weight, Via::Shortcut(a, b));
+        }
+        for u in contraction.neighbours(v) {
+            contracted_neighbours[u] += 1;
+        }
+        contraction.contracted[v] = true;
+        rank[v] = next_rank;
+        next_rank += 1;
+    }
+
+    // every arc is searched from its lower end, forwards from the source or backwards from the
+    // target
+    let mut up = vec![Vec::new(); ids.len()];
+    let mut down = vec![Vec::new(); ids.len()];
+    for (i, arc) in contraction.arcs.iter().enumerate() {
+        if rank[arc.to] > rank[arc.from] {
+            up[arc.from].push(i);
+        } else {
+            down[arc.to].push(i);
+        }
+    }
+
+    ContractionHierarchy {
+        graph,
+        ids,
+        index,
+        arcs: contraction.arcs,
+        up,
+        down,
+    }
+}
+
+pub struct ContractionHierarchy<'a, G, W>
+where
+    G: DirectedGraph,
+{
+    graph: &'a G,
+    ids: Vec<G::NId>,
+    index: NodeHashMap<G, usize>,
+    arcs: Vec<Arc<G::EId, W>>,
+    // arcs leading to a higher ranked node, by source, and arcs coming from one, by target
+    up: Vec<Vec<usize>>,
+    down: Vec<Vec<usize>>,
+}
+
+impl<'a, G, W> ContractionHierarchy<'a, G, W>
+where
+    G: DirectedGraph,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    // Number of shortcuts added on top of the edges of the graph
+    pub fn shortcuts(&self) -> usize {
+        self.arcs
+            .iter()
+            .filter(|arc| matches!(arc.via, Via::Shortcut(..)))
+            .count()
+    }
+
+    // Query object with buffers sized for the graph, to be reused for many queries
+    pub fn query(&self) -> ChQuery<'_, 'a, G, W> {
+        let n = self.ids.len();
+        ChQuery {
+            hierarchy: self,
+            forward: Side::new(n),
+            backward: Side::new(n),
+        }
+    }
+}
+
+pub struct ChQuery<'h, 'a, G, W>
+where
+    G: DirectedGraph,
+{
+    hierarchy: &'h ContractionHierarchy<'a, G, W>,
+    forward: Side<W>,
+    backward: Side<W>,
+}
+
+impl<'h, 'a, G, W> ChQuery<'h, 'a, G, W>
+where
+    G: DirectedGraph,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    pub fn distance(&mut self, start: G::NId, target: G::NId) -> Result<W, AlgoError> {
+        let (_, distance) = self.search(start, target)?;
+        Ok(distance)
+    }
+
+    // Shortest path from start to target with its shortcuts unpacked into edges of the graph
+    pub fn path(&mut self, start: G::NId, target: G::NId) -> Result<(Path<'a, G>, W), AlgoError> {
+        let (meet, distance) = self.search(start, target)?;
+        let h = self.hierarchy;
+
+        // arcs from start up to the meeting node and from there down to target
+        let mut arcs = Vec::new();
+        let mut v = meet;
+        while let Some(arc) = self.forward.parent[v] {
+            arcs.push(arc);
+            v = h.arcs[arc].from;
+        }
+        arcs.reverse();
+        let mut v = meet;
+        while let Some(arc) = self.backward.parent[v] {
+            arcs.push(arc);
+            v = h.arcs[arc].to;
+        }
+
+        let mut steps = vec![(None, h.graph.node(start).unwrap())];
+        let mut stack: Vec<usize> = arcs.into_iter().rev().collect();
+        while let Some(arc) = stack.pop() {
+            match h.arcs[arc].via {
+                Via::Edge(id) => {
+                    let edge = h.graph.edge(id).unwrap();
+                    steps.push((Some(edge), h.graph.node(h.ids[h.arcs[arc].to]).unwrap()));
+                }
+                Via::Shortcut(first, second) => stack.extend([second, first]),
+            }
+        }
+        Ok((Path::new(steps), distance))
+    }
+
+    // Bidirectional upward search, returns the node where the shortest path peaks and its length
+    fn search(&mut self, start: G::NId, target: G::NId) -> Result<(usize, W), AlgoError> {
+        let h = self.hierarchy;
+        let &s = h
+            .index
+            .get(&start)
+            .ok_or_else(|| AlgoError::StartNodeNotFound(format!("{:?}", start)))?;
+        let &t = h
+            .index
+            .get(&target)
+            .ok_or_else(|| AlgoError::TargetNodeNotFound(format!("{:?}", target)))?;
+
+        self.forward.reset(s);
+        self.backward.reset(t);
+        let mut best: Option<(W, usize)> = None;
+        loop {
+            let forward_top = self.forward.top();
+            let backward_top = self.backward.top();
+            let go_forward = match (&forward_top, &backward_top) {
+                (None, None) => break,
+                (Some(f), Some(b)) => f <= b,
+                (f, _) => f.is_some(),
+            };
+            let (side, other, arcs, top) = if go_forward {
+                (&mut self.forward, &self.backward, &h.up, forward_top)
+            } else {
+                (&mut self.backward, &self.forward, &h.down, backward_top)
+            };
+            if best
+                .as_ref()
+                .is_some_and(|(b, _)| top.as_ref().unwrap() >= b)
+            {
+                side.heap.clear();
+                continue;
+            }
+
+            let Some((d, u)) = side.pop() else {
+                continue;
+            };
+            if let Some(other_d) = &other.dist[u] {
+                let through = d.clone() + other_d.clone();
+                if best.as_ref().is_none_or(|(b, _)| through < *b) {
+                    best = Some((through, u));
+                }
+            }
+            for &a in &arcs[u] {
+                let arc = &h.arcs[a];
+                let v = if go_forward { arc.to } else { arc.from };
+                side.relax(v, d.clone() + arc.weight.clone(), a);
+            }
+        }
+
+        best.map(|(distance, meet)| (meet, distance))
+            .ok_or_else(|| {
+                AlgoError::NoPathFromStartToEnd(format!("{:?}", start), format!("{:?}", target))
+            })
+    }
+}
+
+enum Via<EId> {
+    Edge(EId),
+    // the two arcs the shortcut stands for, in order
+    Shortcut(usize, usize),
+}
+
+struct Arc<EId, W> {
+    from: usize,
+    to: usize,
+    weight: W,
+    via: Via<EId>,
+}
+
+struct Contraction<EId, W> {
+    arcs: Vec<Arc<EId, W>>,
+    out: Vec<Vec<usize>>,
+    inc: Vec<Vec<usize>>,
+    contracted: Vec<bool>,
+}
+
+impl<EId, W> Contraction<EId, W>
+where
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    fn add_arc(&mut self, from: usize, to: usize, weight: W, via: Via<EId>) {
+        let index = self.arcs.len();
+        self.out[from].push(index);
+        self.inc[to].push(index);
+        self.arcs.push(Arc {
+            from,
+            to,
+            weight,
+            via,
+        });
+    }
+
+    fn in_arcs(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.inc[v]
+            .iter()
+            .copied()
+            .filter(|&a| !self.contracted[self.arcs[a].from])
+    }
+
+    fn out_arcs(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.out[v]
+            .iter()
+            .copied()
+            .filter(|&a| !self.contracted[self.arcs[a].to])
+    }
+
+    fn neighbours(&self, v: usize) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = self
+            .in_arcs(v)
+            .map(|a| self.arcs[a].from)
+            .chain(self.out_arcs(v).map(|a| self.arcs[a].to))
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn edge_difference(&self, v: usize, shortcuts: &[(usize, usize)]) -> isize {
+        let removed = self.in_arcs(v).count() + self.out_arcs(v).count();
+        shortcuts.len() as isize - removed as isize
+    }
+
+    // Pairs of in and out arcs of v whose combination no other path between their ends beats
+    fn shortcuts(&self, v: usize) -> Vec<(usize, usize)> {
+        let mut shortcuts = Vec::new();
+        let outs: Vec<usize> = self.out_arcs(v).collect();
+        for a in self.in_arcs(v) {
+            let u = self.arcs[a].from;
+            let through: Vec<(usize, W)> = outs
+                .iter()
+                .filter(|&&b| self.arcs[b].to != u)
+                .map(|&b| (b, self.arcs[a].weight.clone() + self.arcs[b].weight.clone()))
+                .collect();
+            let Some(limit) = through.iter().map(|(_, w)| w).max() else {
+                continue;
+            };
+            let dist = self.witness_search(u, v, limit);
+            for (b, w) in through {
+                if dist.get(&self.arcs[b].to).is_none_or(|d| *d > w) {
+                    shortcuts.push((a, b));
+                }
+            }
+        }
+        shortcuts
+    }
+
+    // Distances from u among the remaining nodes except v, as far as limit
+    fn witness_search(&self, u: usize, v: usize, limit: &W) -> AHashMap<usize, W> {
+        let mut dist = AHashMap::from([(u, W::default())]);
+        let mut heap: DaryHeap<Reverse<(W, usize)>, 4> =
+            DaryHeap::from([Reverse((W::default(), u))]);
+        let mut settled = 0;
+        while let Some(Reverse((d, x))) = heap.pop() {
+            if dist.get(&x).is_some_and(|best| d > *best) {
+                continue;
+            }
+            settled += 1;
+            if d > *limit || settled > WITNESS_SETTLE_LIMIT {
+                break;
+            }
+            for a in self.out_arcs(x) {
+                let y = self.arcs[a].to;
+                if y == v {
+                    continue;
+                }
+                let candidate = d.clone() + self.arcs[a].weight.clone();
+                if dist.get(&y).is_none_or(|best| candidate < *best) {
+                    dist.insert(y, candidate.clone());
+                    heap.push(Reverse((candidate, y)));
+                }
+            }
+        }
+        dist
+    }
+}
+
+// One direction of a query. Only the entries that were touched get cleared for the next one
+struct Side<W> {
+    dist: Vec<Option<W>>,
+    parent: Vec<Option<usize>>,
+    touched: Vec<usize>,
+    heap: DaryHeap<Reverse<(W, usize)>, 4>,
+}
+
+impl<W> Side<W>
+where
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    fn new(n: usize) -> Self {
+        Side {
+            dist: vec![None; n],
+            parent: vec![None; n],
+            touched: Vec::new(),
+            heap: DaryHeap::new(),
+        }
+    }
+
+    fn reset(&mut self, source: usize) {
+        for v in self.touched.drain(..) {
+            self.dist[v] = None;
+            self.parent[v] = None;
+        }
+        self.heap.clear();
+        self.dist[source] = Some(W::default());
+        self.touched.push(source);
+        self.heap.push(Reverse((W::default(), source)));
+    }
+
+    fn top(&mut self) -> Option<W> {
+        while let Some(Reverse((d, v))) = self.heap.peek() {
+            if self.dist[*v].as_ref().is_some_and(|best| d > best) {
+                self.heap.pop();
+            } else {
+                return Some(d.clone());
+            }
+        }
+        None
+    }
+
+    fn pop(&mut self) -> Option<(W, usize)> {
+        self.heap.pop().map(|Reverse(item)| item)
+    }
+
+    fn relax(&mut self, v: usize, candidate: W, arc: usize) {
+        if self.dist[v].as_ref().is_some_and(|best| candidate >= *best) {
+            return;
+        }
+        if self.dist[v].is_none() {
+            self.touched.push(v);
+        }
+        self.dist[v] = Some(candidate.clone());
+        self.parent[v] = Some(arc);
+        self.heap.push(Reverse((candidate, v)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::contraction_hierarchies::contraction_hierarchy;
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::DiFlatGraph;
+    use crate::iter::traits::WeightedPathTree;
+
+    // 8 x 8 grid of two way streets with pseudo random travel times, and a few one way streets
+    fn city() -> DiFlatGraph<(), u32> {
+        let mut edges = Vec::new();
+        let mut seed = 17u32;
+        let mut minutes = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % 9 + 1
+        };
+        for i in 0..8 {
+            for j in 0..8 {
+                let u = 8 * i + j;
+                if j < 7 {
+                    edges.push((u, u + 1, minutes()));
+                    edges.push((u + 1, u, minutes()));
+                }
+                if i < 7 {
+                    edges.push((u, u + 8, minutes()));
+                    if (i + j) % 3 != 0 {
+                        edges.push((u + 8, u, minutes()));
+                    }
+                }
+            }
+        }
+        DiFlatGraph::from_ordinal(vec![(); 64], edges)
+    }
+
+    #[test]
+    fn ch_matches_dijkstra() {
+        let graph = city();
+        let hierarchy = contraction_hierarchy(&graph);
+        let mut query = hierarchy.query();
+        for s in 0..64 {
+            let tree = WeightedPathTree::from(dijkstra(&graph, s).unwrap());
+            for t in 0..64 {
+                let expected = *tree.weight(t).unwrap();
+                assert_eq!(query.distance(s, t).unwrap(), expected);
+
+                let (path, distance) = query.path(s, t).unwrap();
+                assert_eq!(distance, expected);
+                let nodes: Vec<_> = path.nodes().map(|node| node.id()).collect();
+                assert_eq!((nodes[0], nodes[nodes.len() - 1]), (s, t));
+                let mut walked = 0;
+                for (edge, pair) in path.edges().zip(nodes.windows(2)) {
+                    assert_eq!((edge.u(), edge.v()), (pair[0], pair[1]));
+                    walked += edge.data();
+                }
+                assert_eq!(walked, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn ch_unreachable() {
+        let graph = DiFlatGraph::<(), u32>::from_ordinal(
+            vec![(); 4],
+            vec![(0, 1, 2), (1, 2, 2), (0, 2, 5), (3, 0, 1)],
+        );
+        let hierarchy = contraction_hierarchy(&graph);
+        let mut query = hierarchy.query();
+        assert_eq!(query.distance(3, 2), Ok(5));
+        assert_eq!(query.distance(0, 0), Ok(0));
+        assert_eq!(
+            query.distance(2, 0),
+            Err(AlgoError::NoPathFromStartToEnd("2".into(), "0".into()))
+        );
+        assert!(matches!(
+            query.distance(0, 9),
+            Err(AlgoError::TargetNodeNotFound(..))
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::algo::errors::AlgoError;
+use crate::algo::shortest_paths::weighted_astar::Open;
+use crate::graph::edge_traits::Weighted;
+use crate::graph::traits::{DirectedGraph, GraphMut};
+use crate::graph::types::NodeHashMap;
+use crate::iter::traits::Path;
+
+use dary_heap::DaryHeap;
+use std::ops::Add;
+
+// D* Lite planner, which keeps its search between queries so that after edges change or the start
+// moves only the part of the search they affect is redone. The search runs backwards from the goal,
+// with heuristic(u, v) estimating the distance from u to v without ever overestimating it. Without
+// calls to move_to this is LPA* with the roles of start and goal swapped.
+//
+// Change edges through graph_mut and then report every edge whose weight changed or that was
+// inserted to update_edge, and the source of every removed edge as well as every removed node to
+// update_node
+pub struct DStarLite<G, W, H>
+where
+    G: GraphMut + DirectedGraph,
+{
+    graph: G,
+    start: G::NId,
+    goal: G::NId,
+    heuristic: H,
+    // start at the time of the last move_to, and how much the heuristic to the start has shrunk
+    // in total since the search began
+    last: G::NId,
+    shift: W,
+    // distance to the goal as of the last expansion, and as a one step lookahead from the
+    // neighbours. Missing entries are infinite
+    g: NodeHashMap<G, W>,
+    rhs: NodeHashMap<G, W>,
+    // key of every node in the queue, heap entries with another key are stale
+    open: NodeHashMap<G, (W, W)>,
+    heap: DaryHeap<Open<G::NId, (W, W)>, 4>,
+}
+
+impl<G, W, H> DStarLite<G, W, H>
+where
+    G: GraphMut + DirectedGraph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+    H: Fn(G::NId, G::NId) -> W,
+{
+    pub fn new(graph: G, start: G::NId, goal: G::NId, heuristic: H) -> Result<Self, AlgoError> {
+        if !graph.contains_node(start) {
+            return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+        }
+        if !graph.contains_node(goal) {
+            return Err(AlgoError::TargetNodeNotFound(format!("{:?}", goal)));
+        }
+        let mut planner = DStarLite {
+            graph,
+            start,
+            goal,
+            heuristic,
+            last: start,
+            shift: W::default(),
+            g: NodeHashMap::<G, W>::default(),
+            rhs: NodeHashMap::<G, W>::default(),
+            open: NodeHashMap::<G, (W, W)>::default(),
+            heap: DaryHeap::new(),
+        };
+        planner.rhs.insert(goal, W::default());
+        planner.push(goal);
+        Ok(planner)
+    }
+
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut G {
+        &mut self.graph
+    }
+
+    pub fn into_graph(self) -> G {
+        self.graph
+    }
+
+    pub fn start(&self) -> G::NId {
+        self.start
+    }
+
+    // Moves the start, typically to the next node of the path as the robot follows it
+    pub fn move_to(&mut self, start: G::NId) -> Result<(), AlgoError> {
+        if !self.graph.contains_node(start) {
+            return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+        }
+        self.shift = self.shift.clone() + (self.heuristic)(self.last, start);
+        self.last = start;
+        self.start = start;
+        Ok(())
+    }
+
+    pub fn update_edge(&mut self, id: G::EId) -> Result<(), AlgoError> {
+        let u = self
+            .graph
+            .edge(id)
+            .ok_or_else(|| AlgoError::EdgeNotFound(format!("{:?}", id)))?
+            .u();
+        self.update_vertex(u);
+        Ok(())
+    }
+
+    // Looks at the edges out of u again. A node that was removed from the graph is forgotten
+    pub fn update_node(&mut self, u: G::NId) {
+        if self.graph.contains_node(u) {
+            self.update_vertex(u);
+        } else {
+            self.g.remove(&u);
+            self.rhs.remove(&u);
+            self.open.remove(&u);
+        }
+    }
+
+    // Brings the search up to date and returns the distance from start to goal, None if the goal
+    // can't be reached
+    pub fn plan(&mut self) -> Option<W> {
+        while let Some(top) = self.heap.peek() {
+            if self.open.get(&top.id) != Some(&top.key) {
+                self.heap.pop();
+                continue;
+            }
+            let start_done = self.rhs.get(&self.start) == self.g.get(&self.start)
+                && self
+                    .key(self.start)
+                    .is_some_and(|start_key| top.key >= start_key);
+            if start_done {
+                break;
+            }
+
+            let Open { key, id: u } = self.heap.pop().unwrap();
+            self.open.remove(&u);
+            let current = self.key(u).unwrap();
+            if key < current {
+                self.open.insert(u, current.clone());
+                self.heap.push(Open {
+                    key: current,
+                    id: u,
+                });
+            } else if less(self.rhs.get(&u), self.g.get(&u)) {
+                self.g.insert(u, self.rhs[&u].clone());
+                self.update_predecessors(u);
+            } else {
+                self.g.remove(&u);
+                self.update_vertex(u);
+                self.update_predecessors(u);
+            }
+        }
+        self.g.get(&self.start).cloned()
+    }
+
+    // Path from start to goal through the search as of the last plan, taking the edge with the
+    // smallest weight plus distance to the goal at every step
+    pub fn path(&self) -> Option<Path<'_, G>> {
+        let mut steps = vec![(None, self.graph.node(self.start).unwrap())];
+        let mut seen = NodeHashMap::<G, ()>::default();
+        let mut u = self.start;
+        while u != self.goal {
+            seen.insert(u, ());
+            let (edge, node) = self
+                .graph
+                .out_edges(u)
+                .unwrap()
+                .filter_map(|(edge, node)| {
+                    let through = edge.data().weight() + self.g.get(&edge.v())?.clone();
+                    Some((through, edge, node))
+                })
+                .min_by(|(a, _, _), (b, _, _)| a.cmp(b))
+                .map(|(_, edge, node)| (edge, node))?;
+            u = edge.v();
+            if seen.contains_key(&u) {
+                return None;
+            }
+            steps.push((Some(edge), node));
+        }
+        Some(Path::new(steps))
+    }
+
+    // Smaller keys get expanded first. Only nodes with a finite g or rhs have a key
+    fn key(&self, u: G::NId) -> Option<(W, W)> {
+        let distance = match (self.g.get(&u), self.rhs.get(&u)) {
+            (Some(g), Some(rhs)) => g.min(rhs).clone(),
+            (Some(d), None) | (None, Some(d)) => d.clone(),
+            (None, None) => return None,
+        };
+        let estimate = distance.clone() + (self.heuristic)(self.start, u) + self.shift.clone();
+        Some((estimate, distance))
+    }
+
+    fn push(&mut self, u: G::NId) {
+        let key = self.key(u).unwrap();
+        self.open.insert(u, key.clone());
+        self.heap.push(Open { key, id: u });
+    }
+
+    fn update_vertex(&mut self, u: G::NId) {
+        if u != self.goal {
+            let best = self
+                .graph
+                .out_edges(u)
+                .unwrap()
+                .filter_map(|(edge, _)| Some(edge.data().weight() + self.g.get(&edge.v())?.clone()))
+                .min();
+            match best {
+                Some(rhs) => self.rhs.insert(u, rhs),
+                None => self.rhs.remove(&u),
+            };
+        }
+        self.open.remove(&u);
+        if self.g.get(&u) != self.rhs.get(&u) {
+            self.push(u);
+        }
+    }
+
+    fn update_predecessors(&mut self, u: G::NId) {
+        let predecessors: Vec<G::NId> = self
+            .graph
+            .in_edges(u)
+            .unwrap()
+            .map(|(edge, _)| edge.u())
+            .collect();
+        for p in predecessors {
+            self.update_vertex(p);
+        }
+    }
+}
+
+// a < b where None is infinite
+fn less<W: Ord>(a: Option<&W>, b: Option<&W>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a < b,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::d_star_lite::DStarLite;
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::graph::grid::Position;
+    use crate::graph::grid_map::GridMap;
+    use crate::graph::traits::{Graph, GraphMut};
+    use crate::graph::types::DiMapGraph;
+    use crate::iter::traits::WeightedPathTree;
+
+    fn shortest(
+        graph: &DiMapGraph<Position, char, u32>,
+        start: Position,
+        goal: Position,
+    ) -> Option<u32> {
+        WeightedPathTree::from(dijkstra(graph, start).unwrap())
+            .weight(goal)
+            .copied()
+    }
+
+    // Blocks u -> v and v -> u by making them very expensive, like a sensor spotting an obstacle
+    fn block(
+        planner: &mut DStarLite<
+            DiMapGraph<Position, char, u32>,
+            u32,
+            impl Fn(Position, Position) -> u32,
+        >,
+        u: Position,
+        v: Position,
+    ) {
+        for (a, b) in [(u, v), (v, u)] {
+            let id = planner.graph().between(a, b).unwrap().id();
+            *planner.graph_mut().edge_mut(id).unwrap().data() = 1000;
+            planner.update_edge(id).unwrap();
+        }
+    }
+
+    #[test]
+    fn replan_after_changes() {
+        let map = GridMap::parse(
+            "\
+S.......
+.######.
+........
+.######.
+.......E",
+        )
+        .unwrap();
+        let (start, goal) = (map.special('S').unwrap(), map.special('E').unwrap());
+        let graph = map.into_graph();
+        let mut planner = DStarLite::new(graph, start, goal, |u: Position, v: Position| {
+            u.manhattan(v) as u32
+        })
+        .unwrap();
+        assert_eq!(planner.plan(), Some(11));
+
+        // close the left column between the first two corridors, then the right one further down
+        block(&mut planner, Position::from((1, 0)), Position::from((2, 0)));
+        assert_eq!(planner.plan(), Some(11));
+        assert_eq!(
+            planner.path().unwrap().nodes().nth(1).unwrap().id(),
+            Position::from((0, 1))
+        );
+
+        planner.move_to(Position::from((0, 5))).unwrap();
+        block(&mut planner, Position::from((3, 7)), Position::from((4, 7)));
+        let distance = planner.plan();
+        assert_eq!(distance, Some(20));
+        assert_eq!(
+            distance,
+            shortest(planner.graph(), Position::from((0, 5)), goal)
+        );
+        let path = planner.path().unwrap();
+        let walked: u32 = path.edges().map(|edge| *edge.data()).sum();
+        assert_eq!(Some(walked), distance);
+        assert_eq!(path.nodes().last().unwrap().id(), goal);
+
+        // removing an edge is reported through its source
+        let id = planner
+            .graph()
+            .between(Position::from((2, 0)), Position::from((3, 0)))
+            .unwrap()
+            .id();
+        planner.graph_mut().remove_edge(id).unwrap();
+        planner.update_node(Position::from((2, 0)));
+        assert_eq!(
+            planner.plan(),
+            shortest(planner.graph(), Position::from((0, 5)), goal)
+        );
+
+        assert!(matches!(
+            planner.move_to(Position::from((1, 1))),
+            Err(AlgoError::StartNodeNotFound(..))
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::algo::errors::AlgoError;
 use crate::graph::edge::Edge;
+use crate::graph::edge_traits::Weighted;
 use crate::graph::node::Node;
 use crate::graph::traits::Graph;
 use crate::iter::pfs::{pfs, Pfs, PriorityType};
@@ -9,21 +10,22 @@ use std::cmp::Ord;
 use std::default::Default;
 use std::ops::Add;
 
-pub fn dijkstra<'a, G>(
+pub fn dijkstra<'a, G, W>(
     graph: &'a G,
     start: G::NId,
 ) -> Result<
     Pfs<
         G,
-        G::E,
-        impl Fn(G::E, &Edge<'a, G::NId, G::EId, G::E>, &Node<'a, G::NId, G::N>) -> G::E,
+        W,
+        impl Fn(W, &Edge<'a, G::NId, G::EId, G::E>, &Node<'a, G::NId, G::N>) -> W,
         impl Fn(&Edge<'a, G::NId, G::EId, G::E>, &Node<'a, G::NId, G::N>) -> bool,
     >,
     AlgoError,
 >
 where
     G: Graph,
-    G::E: Add<Output = G::E> + Ord + Default + Clone,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
 {
     if !graph.contains_node(start) {
         return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
@@ -31,9 +33,9 @@ where
     Ok(pfs(
         graph,
         start,
-        G::E::default(),
+        W::default(),
         PriorityType::Min,
-        |dist, edge, _| dist + edge.data().clone(),
+        |dist, edge, _| dist + edge.data().weight(),
     ))
 }
 
@@ -41,6 +43,7 @@ where
 mod tests {
     use crate::algo::errors::AlgoError;
     use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::graph::edge_traits::Weighted;
     use crate::graph::traits::{GraphMut, KeyedGraph, OrdinalGraph, WithCapacity};
     use crate::graph::types::{DiListGraph, UnMapGraph};
     use crate::iter::traits::{Tree, WeightedPathTree};
@@ -170,4 +173,41 @@ mod tests {
             AlgoError::StartNodeNotFound(..)
         ));
     }
+
+    struct Road {
+        name: &'static str,
+        minutes: u32,
+    }
+
+    impl Weighted<u32> for Road {
+        fn weight(&self) -> u32 {
+            self.minutes
+        }
+
+        fn set_weight(&mut self, val: u32) {
+            self.minutes = val;
+        }
+    }
+
+    #[test]
+    fn dijkstra_weighted_edge_data() {
+        let mut graph = UnMapGraph::with_capacity(3, 3);
+        graph.put_node("A", ());
+        graph.put_node("B", ());
+        graph.put_node("C", ());
+        let road = |name, minutes| Road { name, minutes };
+        graph
+            .insert_edge("A", "B", road("highway", 10))
+            .expect("nodes should exist");
+        graph
+            .insert_edge("A", "C", road("main street", 4))
+            .expect("nodes should exist");
+        graph
+            .insert_edge("C", "B", road("side street", 3))
+            .expect("nodes should exist");
+
+        let tree = WeightedPathTree::from(dijkstra(&graph, "A").unwrap());
+        assert_eq!(tree.weight("B"), Some(&7));
+        assert_eq!(tree.parent_edge("B").unwrap().name, "side street");
+    }
 }
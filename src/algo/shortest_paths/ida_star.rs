@@ -0,0 +1,170 @@
+use crate::algo::errors::AlgoError;
+use crate::graph::edge_traits::Weighted;
+use crate::graph::node::Node;
+use crate::graph::traits::Graph;
+use crate::graph::types::NodeHashMap;
+use crate::iter::traits::Path;
+
+use std::ops::Add;
+
+// Iterative deepening A*. Runs depth first searches that give up on a branch once distance plus
+// heuristic exceeds a bound, raising the bound to the smallest value that went over it until goal
+// is reached. Only the current path is kept in memory, so it suits huge implicit graphs, at the
+// price of expanding nodes again in every round. With an admissible heuristic the path is a
+// shortest one
+pub fn ida_star<'a, G, W>(
+    graph: &'a G,
+    start: G::NId,
+    goal: G::NId,
+    heuristic: impl Fn(&Node<'a, G::NId, G::N>) -> W,
+) -> Result<(Path<'a, G>, W), AlgoError>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    if !graph.contains_node(start) {
+        return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+    }
+    if !graph.contains_node(goal) {
+        return Err(AlgoError::TargetNodeNotFound(format!("{:?}", goal)));
+    }
+    let root = || graph.node(start).unwrap();
+    if start == goal {
+        return Ok((Path::new(vec![(None, root())]), W::default()));
+    }
+
+    let mut bound = heuristic(&root());
+    loop {
+        // path[i] is reached through the edges of stack[i - 1], and stack[i] holds the edges of
+        // path[i] that are left to try
+        let mut path = vec![(None, root(), W::default())];
+        let mut stack = vec![graph.adj(start).unwrap()];
+        let mut on_path = NodeHashMap::<G, ()>::default();
+        on_path.insert(start, ());
+        let mut next_bound: Option<W> = None;
+
+        while let Some(edges) = stack.last_mut() {
+            let Some((edge, node)) = edges.next() else {
+                stack.pop();
+                let (_, node, _) = path.pop().unwrap();
+                on_path.remove(&node.id());
+                continue;
+            };
+            if on_path.contains_key(&node.id()) {
+                continue;
+            }
+
+            let distance = path.last().unwrap().2.clone() + edge.data().weight();
+            let estimate = distance.clone() + heuristic(&node);
+            if estimate > bound {
+                if next_bound.as_ref().is_none_or(|b| estimate < *b) {
+                    next_bound = Some(estimate);
+                }
+                continue;
+            }
+
+            let id = node.id();
+            path.push((Some(edge), node, distance.clone()));
+            if id == goal {
+                let path = path.into_iter().map(|(edge, node, _)| (edge, node));
+                return Ok((Path::new(path.collect()), distance));
+            }
+            on_path.insert(id, ());
+            stack.push(graph.adj(id).unwrap());
+        }
+
+        match next_bound {
+            Some(b) => bound = b,
+            None => {
+                return Err(AlgoError::NoPathFromStartToEnd(
+                    format!("{:?}", start),
+                    format!("{:?}", goal),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::astar::astar;
+    use crate::algo::shortest_paths::ida_star::ida_star;
+    use crate::graph::node::Node;
+    use crate::graph::state::StateGraph;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::DiListGraph;
+    use itertools::Itertools;
+
+    type Board = [u8; 9];
+
+    const SOLVED: Board = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+
+    fn slide(board: Board) -> Vec<Board> {
+        let blank = board.iter().position(|&tile| tile == 0).unwrap();
+        let (i, j) = (blank / 3, blank % 3);
+        [(0, 1), (2, 1), (1, 0), (1, 2)]
+            .into_iter()
+            .filter_map(|(di, dj)| Some(((i + di).checked_sub(1)?, (j + dj).checked_sub(1)?)))
+            .filter(|&(k, l)| k < 3 && l < 3)
+            .map(|(k, l)| {
+                let mut next = board;
+                next.swap(blank, 3 * k + l);
+                next
+            })
+            .collect()
+    }
+
+    // Sum of the manhattan distances of the tiles to where they belong
+    fn manhattan(node: &Node<Board, ()>) -> u32 {
+        node.id()
+            .iter()
+            .enumerate()
+            .filter(|(_, &tile)| tile != 0)
+            .map(|(at, &tile)| {
+                let home = tile as usize - 1;
+                ((at / 3).abs_diff(home / 3) + (at % 3).abs_diff(home % 3)) as u32
+            })
+            .sum()
+    }
+
+    #[test]
+    fn ida_star_eight_puzzle() {
+        let puzzle = StateGraph::new(|_| (), |_, _| 1u32, slide, |_| true);
+        let start = [8, 6, 7, 2, 5, 4, 3, 0, 1];
+
+        let (path, moves) = ida_star(&puzzle, start, SOLVED, manhattan).unwrap();
+        let (_, _, weight) = astar(&puzzle, start, manhattan)
+            .unwrap()
+            .find(|(_, node, _)| node.id() == SOLVED)
+            .unwrap();
+        assert_eq!(moves, weight.distance);
+        assert_eq!(path.edges().count() as u32, moves);
+        let boards = path.nodes().map(|node| node.id()).collect_vec();
+        assert_eq!((boards[0], boards[boards.len() - 1]), (start, SOLVED));
+        assert!(boards
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| slide(*a).contains(b)));
+    }
+
+    #[test]
+    fn ida_star_no_path() {
+        let graph = DiListGraph::<(), u32>::from_ordinal(
+            vec![(); 4],
+            vec![(0, 1, 1), (1, 0, 1), (1, 2, 5), (3, 2, 1)],
+        );
+        let (path, weight) = ida_star(&graph, 0, 2, |_| 0).unwrap();
+        assert_eq!(weight, 6);
+        assert_eq!(path.nodes().map(|node| node.id()).collect_vec(), [0, 1, 2]);
+        assert!(matches!(
+            ida_star(&graph, 0, 3, |_| 0),
+            Err(AlgoError::NoPathFromStartToEnd(..))
+        ));
+        assert!(matches!(
+            ida_star(&graph, 0, 7, |_| 0),
+            Err(AlgoError::TargetNodeNotFound(..))
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::algo::errors::AlgoError;
+use crate::graph::grid::Position;
+use crate::graph::traits::Graph;
+use crate::utils::float::F64;
+
+use ahash::AHashMap;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Jump point search on an 8-connected grid where every cell the graph contains is walkable,
+// straight moves cost 1 and diagonal moves sqrt(2). Diagonal moves may not cut corners, so both
+// cells beside them have to be walkable. Instead of expanding every cell like astar does, straight
+// and diagonal runs are skipped up to the cells where an optimal path might turn. Returns every
+// cell on a shortest path from start to goal together with its length.
+//
+// Runs are cut short after as many cells as the bounding box of start, goal and the jump points
+// expanded so far is wide, so that on unbounded grids jumps into open space come back. The cell a
+// run stops at becomes a jump point, which splits the run but still finds a shortest path
+pub fn jump_point_search<G>(
+    graph: &G,
+    start: Position,
+    goal: Position,
+) -> Result<(Vec<Position>, F64), AlgoError>
+where
+    G: Graph<NId = Position>,
+{
+    if !graph.contains_node(start) {
+        return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+    }
+    if !graph.contains_node(goal) {
+        return Err(AlgoError::TargetNodeNotFound(format!("{:?}", goal)));
+    }
+
+    let walkable = |p: Position| graph.contains_node(p);
+    let mut distance = AHashMap::from([(start, F64::new(0.0))]);
+    let mut parent: AHashMap<Position, Position> = AHashMap::new();
+    // positions go on the heap as plain tuples to break ties
+    let mut heap: BinaryHeap<(Reverse<F64>, (isize, isize))> =
+        BinaryHeap::from([(Reverse(start.octile(goal)), start.into())]);
+    let mut explored = Bounds::new(start, goal);
+    while let Some((_, p)) = heap.pop() {
+        let p = Position::from(p);
+        if p == goal {
+            return Ok((unfold(&parent, goal), distance[&goal]));
+        }
+        explored.include(p);
+        let from = parent.get(&p).map(|&q| direction(q, p));
+        for (di, dj) in successors(&walkable, p, from) {
+            let Some(next) = jump(&walkable, p, (di, dj), goal, explored.size()) else {
+                continue;
+            };
+            let d = distance[&p] + p.octile(next);
+            if distance.get(&next).is_none_or(|&best| d < best) {
+                distance.insert(next, d);
+                parent.insert(next, p);
+                heap.push((Reverse(d + next.octile(goal)), next.into()));
+            }
+        }
+    }
+
+    Err(AlgoError::NoPathFromStartToEnd(
+        format!("{:?}", start),
+        format!("{:?}", goal),
+    ))
+}
+
+// Smallest rectangle containing some positions
+struct Bounds {
+    min: (isize, isize),
+    max: (isize, isize),
+}
+
+impl Bounds {
+    fn new(a: Position, b: Position) -> Bounds {
+        let mut bounds = Bounds {
+            min: a.into(),
+            max: a.into(),
+        };
+        bounds.include(b);
+        bounds
+    }
+
+    fn include(&mut self, p: Position) {
+        let (i, j) = p.into();
+        self.min = (self.min.0.min(i), self.min.1.min(j));
+        self.max = (self.max.0.max(i), self.max.1.max(j));
+    }
+
+    // Length of the longer side, at least 1
+    fn size(&self) -> usize {
+        let height = self.max.0.abs_diff(self.min.0);
+        let width = self.max.1.abs_diff(self.min.1);
+        height.max(width).max(1)
+    }
+}
+
+// Unit step from one position towards another along a straight or diagonal line
+fn direction(from: Position, to: Position) -> (isize, isize) {
+    let ((i, j), (k, l)): ((isize, isize), (isize, isize)) = (from.into(), to.into());
+    ((k - i).signum(), (l - j).signum())
+}
+
+fn can_step<W>(walkable: &W, p: Position, (di, dj): (isize, isize)) -> bool
+where
+    W: Fn(Position) -> bool,
+{
+    walkable(p.offset(di, dj))
+        && (di == 0 || dj == 0 || (walkable(p.offset(di, 0)) && walkable(p.offset(0, dj))))
+}
+
+// Directions worth searching from p when it was reached moving in the given direction
+fn successors<W>(walkable: &W, p: Position, from: Option<(isize, isize)>) -> Vec<(isize, isize)>
+where
+    W: Fn(Position) -> bool,
+{
+    let candidates = match from {
+        None => vec![
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+        ],
+        Some((0, dj)) => vec![(0, dj), (-1, 0), (1, 0), (-1, dj), (1, dj)],
+        Some((di, 0)) => vec![(di, 0), (0, -1), (0, 1), (di, -1), (di, 1)],
+        Some((di, dj)) => vec![(di, 0), (0, dj), (di, dj)],
+    };
+    candidates
+        .into_iter()
+        .filter(|&d| can_step(walkable, p, d))
+        .collect()
+}
+
+// Moves from p in the given direction until reaching the goal, a cell where a shortest path
+// might have to turn or the limit of steps, None when running into a wall first
+fn jump<W>(
+    walkable: &W,
+    p: Position,
+    (di, dj): (isize, isize),
+    goal: Position,
+    limit: usize,
+) -> Option<Position>
+where
+    W: Fn(Position) -> bool,
+{
+    let mut cur = p;
+    for step in 1..=limit {
+        if !can_step(walkable, cur, (di, dj)) {
+            return None;
+        }
+        let prev = cur;
+        cur = cur.offset(di, dj);
+        if cur == goal || step == limit {
+            return Some(cur);
+        }
+        let forced = match (di, dj) {
+            (0, _) => [-1, 1]
+                .into_iter()
+                .any(|s| walkable(cur.offset(s, 0)) && !walkable(prev.offset(s, 0))),
+            (_, 0) => [-1, 1]
+                .into_iter()
+                .any(|s| walkable(cur.offset(0, s)) && !walkable(prev.offset(0, s))),
+            // a straight run cut short counts as well, what lies beyond it is unknown
+            _ => {
+                jump(walkable, cur, (di, 0), goal, limit).is_some()
+                    || jump(walkable, cur, (0, dj), goal, limit).is_some()
+            }
+        };
+        if forced {
+            return Some(cur);
+        }
+    }
+    None
+}
+
+// Fills in the cells between consecutive jump points
+fn unfold(parent: &AHashMap<Position, Position>, goal: Position) -> Vec<Position> {
+    let mut path = vec![goal];
+    let mut cur = goal;
+    while let Some(&prev) = parent.get(&cur) {
+        let (di, dj) = direction(cur, prev);
+        while cur != prev {
+            cur = cur.offset(di, dj);
+            path.push(cur);
+        }
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::astar::astar;
+    use crate::algo::shortest_paths::jps::jump_point_search;
+    use crate::graph::grid::{Grid, GridBounds, Position};
+    use crate::utils::float::F64;
+    use itertools::Itertools;
+
+    fn walls() -> impl Fn(Position) -> bool {
+        // 20 x 20 room, with a wall in column 8 open at row 17 and a wall in row 5 open at
+        // column 15
+        let bounds = GridBounds::new(0..20, 0..20);
+        move |p: Position| {
+            let (i, j) = p.into();
+            bounds.check(p) && !(j == 8 && i != 17) && !(i == 5 && j > 8 && j != 15)
+        }
+    }
+
+    #[test]
+    fn jps_matches_astar() {
+        let walkable = walls();
+        let graph = Grid::eight_connected(
+            |_| (),
+            |u: Position, v: Position| u.octile(v),
+            &walkable,
+            |u: Position, v: Position| {
+                let ((i, j), (k, l)): ((isize, isize), (isize, isize)) = (u.into(), v.into());
+                walkable(Position::from((i, l))) && walkable(Position::from((k, j)))
+            },
+        );
+
+        for (start, goal) in [((0, 0), (0, 19)), ((19, 0), (0, 12)), ((3, 3), (3, 4))] {
+            let (start, goal) = (Position::from(start), Position::from(goal));
+            let (path, length) = jump_point_search(&graph, start, goal).unwrap();
+            let (_, _, weight) = astar(&graph, start, |node| node.id().octile(goal))
+                .unwrap()
+                .find(|(_, node, _)| node.id() == goal)
+                .unwrap();
+            assert!(length.approx_eq(weight.distance));
+
+            assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+            let walked: F64 = path.iter().tuple_windows().map(|(u, v)| u.octile(*v)).sum();
+            assert!(walked.approx_eq(length));
+            assert!(path.iter().all(|&p| walkable(p)));
+            assert!(path
+                .iter()
+                .tuple_windows()
+                .all(|(u, v)| u.chebyshev(*v) == 1));
+        }
+    }
+
+    #[test]
+    fn jps_no_path() {
+        let bounds = GridBounds::new(0..5, 0..5);
+        let graph = Grid::eight_connected(
+            |_| (),
+            |_, _| 1,
+            |p: Position| bounds.check(p) && <(isize, isize)>::from(p).1 != 2,
+            |_, _| true,
+        );
+        assert!(matches!(
+            jump_point_search(&graph, Position::from((0, 0)), Position::from((4, 4))),
+            Err(AlgoError::NoPathFromStartToEnd(..))
+        ));
+        assert!(matches!(
+            jump_point_search(&graph, Position::from((0, 0)), Position::from((0, 2))),
+            Err(AlgoError::TargetNodeNotFound(..))
+        ));
+    }
+
+    #[test]
+    fn jps_unbounded() {
+        // endless plane with a wall in column 5 from row -3 to 3
+        let walkable = |p: Position| {
+            let (i, j) = p.into();
+            GridBounds::unbounded().check(p) && !(j == 5 && (-3..=3).contains(&i))
+        };
+        let graph = Grid::eight_connected(
+            |_| (),
+            |u: Position, v: Position| u.octile(v),
+            walkable,
+            |u: Position, v: Position| {
+                let ((i, j), (k, l)): ((isize, isize), (isize, isize)) = (u.into(), v.into());
+                walkable(Position::from((i, l))) && walkable(Position::from((k, j)))
+            },
+        );
+
+        for goal in [(0, 10), (-2, 2), (7, -7)] {
+            let (start, goal) = (Position::from((0, 0)), Position::from(goal));
+            let (path, length) = jump_point_search(&graph, start, goal).unwrap();
+            let (_, _, weight) = astar(&graph, start, |node| node.id().octile(goal))
+                .unwrap()
+                .find(|(_, node, _)| node.id() == goal)
+                .unwrap();
+            assert!(length.approx_eq(weight.distance));
+            assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+            assert!(path.iter().all(|&p| walkable(p)));
+        }
+    }
+
+    #[test]
+    fn grid_heuristics() {
+        let (a, b) = (Position::from((0, 0)), Position::from((3, -5)));
+        assert_eq!(a.manhattan(b), 8);
+        assert_eq!(a.chebyshev(b), 5);
+        assert!(a
+            .octile(b)
+            .approx_eq(F64::new(2.0 + 3.0 * std::f64::consts::SQRT_2)));
+    }
+}
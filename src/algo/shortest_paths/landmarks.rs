@@ -0,0 +1,364 @@
+use crate::algo::shortest_paths::dijkstra::dijkstra;
+use crate::algo::shortest_paths::weighted_astar::Open;
+use crate::graph::edge_traits::Weighted;
+use crate::graph::node::Node;
+use crate::graph::traits::{DirectedGraph, Graph, GraphIter, UndirectedGraph};
+use crate::graph::types::NodeHashMap;
+
+use dary_heap::DaryHeap;
+use std::ops::{Add, Sub};
+
+// Shortest path distances to a node, left out for undirected graphs where they are those from it
+type DistancesTo<G, W> = fn(&G, <G as Graph>::NId) -> NodeHashMap<G, W>;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LandmarkStrategy {
+    // each landmark is the node farthest from the ones picked before it
+    Farthest,
+    // each landmark is a leaf of a shortest path tree, reached by descending into the subtree
+    // whose distances the landmarks picked before it bound worst
+    Avoid,
+}
+
+// Shortest path distances from and to a few landmark nodes, from which ALT (A*, landmarks and
+// triangle inequality) heuristics for astar can be made
+pub struct Landmarks<G, W>
+where
+    G: Graph,
+{
+    landmarks: Vec<G::NId>,
+    from: Vec<NodeHashMap<G, W>>,
+    to: Vec<NodeHashMap<G, W>>,
+}
+
+impl<G, W> Landmarks<G, W>
+where
+    G: Graph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Sub<Output = W> + Ord + Default + Clone,
+{
+    pub fn directed(graph: &G, count: usize, strategy: LandmarkStrategy) -> Self
+    where
+        G: DirectedGraph,
+    {
+        Self::select(graph, count, strategy, Some(distances_to::<G, W>))
+    }
+
+    // Distances from and to a landmark are the same, so only half of them get computed and stored
+    pub fn undirected(graph: &G, count: usize, strategy: LandmarkStrategy) -> Self
+    where
+        G: UndirectedGraph,
+    {
+        Self::select(graph, count, strategy, None)
+    }
+
+    pub fn landmarks(&self) -> &[G::NId] {
+        &self.landmarks
+    }
+
+    // Lower bound on the distance from a node to target, from the triangle inequality with each
+    // landmark. It is consistent, so astar with it still finds shortest paths
+    pub fn heuristic(&self, target: G::NId) -> impl Fn(&Node<'_, G::NId, G::N>) -> W + '_ {
+        let from_target: Vec<Option<W>> =
+            self.from.iter().map(|d| d.get(&target).cloned()).collect();
+        let to_target: Vec<Option<W>> = self.to().iter().map(|d| d.get(&target).cloned()).collect();
+        move |node| {
+            let v = node.id();
+            let mut bound = W::default();
+            for (i, (from, to)) in self.from.iter().zip(self.to()).enumerate() {
+                // d(l, t) - d(l, v) <= d(v, t) and d(v, l) - d(t, l) <= d(v, t)
+                if let (Some(lt), Some(lv)) = (&from_target[i], from.get(&v)) {
+                    bound = bound.max(difference(lt, lv));
+                }
+                if let (Some(vl), Some(tl)) = (to.get(&v), &to_target[i]) {
+                    bound = bound.max(difference(vl, tl));
+                }
+            }
+            bound
+        }
+    }
+
+    // Distances to the landmarks, which are the distances from them when the graph is undirected
+    fn to(&self) -> &[NodeHashMap<G, W>] {
+        if self.to.is_empty() {
+            &self.from
+        } else {
+            &self.to
+        }
+    }
+
+    fn select(
+        graph: &G,
+        count: usize,
+        strategy: LandmarkStrategy,
+        distances_to: Option<DistancesTo<G, W>>,
+    ) -> Self {
+        let mut landmarks = Landmarks {
+            landmarks: Vec::new(),
+            from: Vec::new(),
+            to: Vec::new(),
+        };
+        let Some(first) = graph.nodes().next().map(|node| node.id()) else {
+            return landmarks;
+        };
+        while landmarks.landmarks.len() < count.min(graph.n()) {
+            let next = match strategy {
+                LandmarkStrategy::Farthest => landmarks.farthest(graph, first),
+                LandmarkStrategy::Avoid => landmarks.avoid(graph, first),
+            };
+            let Some(l) = next else {
+                break;
+            };
+            landmarks.landmarks.push(l);
+            landmarks.from.push(distances_from(graph, l));
+            if let Some(distances_to) = distances_to {
+                landmarks.to.push(distances_to(graph, l));
+            }
+        }
+        landmarks
+    }
+
+    // Node that is farthest away from the landmarks there are, taking the distance both ways so
+    // that directed graphs get landmarks on every side. Nodes the landmarks can't reach come first.
+    // Without landmarks yet, the node farthest from first
+    fn farthest(&self, graph: &G, first: G::NId) -> Option<G::NId> {
+        if self.landmarks.is_empty() {
+            return dijkstra(graph, first)
+                .unwrap()
+                .last()
+                .map(|(_, node, _)| node.id());
+        }
+        graph
+            .nodes()
+            .map(|node| node.id())
+            .filter(|v| !self.landmarks.contains(v))
+            .map(|v| {
+                let closest = self
+                    .from
+                    .iter()
+                    .zip(self.to())
+                    .filter_map(|(from, to)| Some(from.get(&v)?.clone() + to.get(&v)?.clone()))
+                    .min();
+                let unreached = closest.is_none();
+                (v, (unreached, closest))
+            })
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(v, _)| v)
+    }
+
+    // Goldberg and Harrelson's avoid: grows a shortest path tree from the node farthest from the
+    // landmarks, weighs every node by how far its lower bound falls short of its distance to the
+    // root, and walks down from the root into the heaviest subtree without a landmark until it
+    // reaches a leaf
+    fn avoid(&self, graph: &G, first: G::NId) -> Option<G::NId> {
+        let root = self.farthest(graph, first)?;
+        let order: Vec<_> = dijkstra(graph, root)
+            .unwrap()
+            .map(|(edge, node, distance)| {
+                let v = node.id();
+                (v, edge.map(|edge| edge.other(v)), distance)
+            })
+            .collect();
+
+        // nodes come out of dijkstra after their parents, so going backwards sums up subtrees before
+        // the nodes above them. A subtree with a landmark has no size
+        let mut size = NodeHashMap::<G, Option<W>>::default();
+        let mut children = NodeHashMap::<G, Vec<G::NId>>::default();
+        for (v, parent, distance) in order.iter().rev() {
+            let below = size.remove(v).unwrap_or(Some(W::default()));
+            let total = if self.landmarks.contains(v) {
+                None
+            } else {
+                let shortfall = self
+                    .lower_bound(root, *v)
+                    .map_or(distance.clone(), |bound| difference(distance, &bound));
+                below.map(|sum| sum + shortfall)
+            };
+            size.insert(*v, total.clone());
+            if let Some(p) = parent {
+                children.entry(*p).or_default().push(*v);
+                let parent_size = size.entry(*p).or_insert(Some(W::default()));
+                *parent_size = match (parent_size.take(), total) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    _ => None,
+                };
+            }
+        }
+        let mut v = root;
+        loop {
+            let next = children
+                .get(&v)
+                .into_iter()
+                .flatten()
+                .filter_map(|c| Some((size.get(c)?.clone()?, *c)))
+                .max_by(|(a, _), (b, _)| a.cmp(b));
+            match next {
+                Some((_, c)) => v = c,
+                None => break,
+            }
+        }
+        (!self.landmarks.contains(&v)).then_some(v)
+    }
+
+    fn lower_bound(&self, u: G::NId, v: G::NId) -> Option<W> {
+        self.from
+            .iter()
+            .zip(self.to())
+            .flat_map(|(from, to)| {
+                let forward = match (from.get(&v), from.get(&u)) {
+                    (Some(lv), Some(lu)) => Some(difference(lv, lu)),
+                    _ => None,
+                };
+                let backward = match (to.get(&u), to.get(&v)) {
+                    (Some(ul), Some(vl)) => Some(difference(ul, vl)),
+                    _ => None,
+                };
+                [forward, backward]
+            })
+            .flatten()
+            .max()
+    }
+}
+
+// a - b, or zero when b is larger
+fn difference<W>(a: &W, b: &W) -> W
+where
+    W: Sub<Output = W> + Ord + Default + Clone,
+{
+    if a > b {
+        a.clone() - b.clone()
+    } else {
+        W::default()
+    }
+}
+
+fn distances_from<G, W>(graph: &G, source: G::NId) -> NodeHashMap<G, W>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    let mut dist = NodeHashMap::<G, W>::default();
+    for (_, node, d) in dijkstra(graph, source).unwrap() {
+        dist.insert(node.id(), d);
+    }
+    dist
+}
+
+// Dijkstra along in edges, so the distances are those to target
+fn distances_to<G, W>(graph: &G, target: G::NId) -> NodeHashMap<G, W>
+where
+    G: DirectedGraph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    let mut dist = NodeHashMap::<G, W>::default();
+    let mut heap = DaryHeap::<_, 4>::from([Open {
+        key: W::default(),
+        id: target,
+    }]);
+    while let Some(Open { key, id: v }) = heap.pop() {
+        if dist.contains_key(&v) {
+            continue;
+        }
+        for (edge, _) in graph.in_edges(v).unwrap() {
+            if !dist.contains_key(&edge.u()) {
+                heap.push(Open {
+                    key: key.clone() + edge.data().weight(),
+                    id: edge.u(),
+                });
+            }
+        }
+        dist.insert(v, key);
+    }
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::shortest_paths::astar::astar;
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::algo::shortest_paths::landmarks::{LandmarkStrategy, Landmarks};
+    use crate::graph::traits::{GraphIter, OrdinalGraph};
+    use crate::graph::types::{DiListGraph, UnListGraph};
+    use crate::iter::traits::WeightedPathTree;
+    use itertools::Itertools;
+
+    // Ring of 40 nodes with pseudo random one way chords
+    fn edges() -> Vec<(usize, usize, u32)> {
+        let mut seed = 5u32;
+        let mut next = move |k: u32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % k
+        };
+        let mut edges = Vec::new();
+        for u in 0..40 {
+            edges.push((u, (u + 1) % 40, next(5) + 1));
+            edges.push(((u + 1) % 40, u, next(5) + 1));
+            edges.push((u, next(40) as usize, next(20) + 3));
+        }
+        edges
+    }
+
+    #[test]
+    fn alt_heuristic_is_a_lower_bound() {
+        let graph = DiListGraph::<(), u32>::from_ordinal(vec![(); 40], edges());
+        for strategy in [LandmarkStrategy::Farthest, LandmarkStrategy::Avoid] {
+            let landmarks = Landmarks::directed(&graph, 4, strategy);
+            assert_eq!(landmarks.landmarks().iter().unique().count(), 4);
+
+            for t in [0, 13, 27] {
+                let h = landmarks.heuristic(t);
+                for v in graph.nodes() {
+                    let tree = WeightedPathTree::from(dijkstra(&graph, v.id()).unwrap());
+                    assert!(h(&v) <= *tree.weight(t).unwrap());
+                }
+                assert_eq!(h(&graph.nodes().nth(t).unwrap()), 0);
+                assert!(graph.nodes().any(|v| h(&v) > 0));
+
+                let (_, _, weight) = astar(&graph, 0, landmarks.heuristic(t))
+                    .unwrap()
+                    .find(|(_, node, _)| node.id() == t)
+                    .unwrap();
+                let tree = WeightedPathTree::from(dijkstra(&graph, 0).unwrap());
+                assert_eq!(weight.distance, *tree.weight(t).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn alt_undirected() {
+        let graph = UnListGraph::<(), u32>::from_ordinal(vec![(); 40], edges());
+        let landmarks = Landmarks::undirected(&graph, 3, LandmarkStrategy::Farthest);
+        let tree = WeightedPathTree::from(dijkstra(&graph, 5).unwrap());
+        let h = landmarks.heuristic(5);
+        for v in graph.nodes() {
+            assert!(h(&v) <= *tree.weight(v.id()).unwrap());
+        }
+        // a landmark's bound to itself is exact
+        let l = landmarks.landmarks()[0];
+        assert_eq!(
+            landmarks.heuristic(l)(&graph.nodes().nth(5).unwrap()),
+            *tree.weight(l).unwrap()
+        );
+
+        // on a plain ring the landmarks spread out just like on the same ring with edges both ways
+        let ring: Vec<_> = (0..40).map(|u| (u, (u + 1) % 40, 1u32)).collect();
+        let both_ways = ring
+            .iter()
+            .flat_map(|&(u, v, w)| [(u, v, w), (v, u, w)])
+            .collect();
+        let undirected = UnListGraph::<(), u32>::from_ordinal(vec![(); 40], ring);
+        let directed = DiListGraph::<(), u32>::from_ordinal(vec![(); 40], both_ways);
+        for strategy in [LandmarkStrategy::Farthest, LandmarkStrategy::Avoid] {
+            assert_eq!(
+                Landmarks::undirected(&undirected, 4, strategy).landmarks(),
+                Landmarks::directed(&directed, 4, strategy).landmarks()
+            );
+        }
+        assert_eq!(
+            Landmarks::undirected(&undirected, 4, LandmarkStrategy::Farthest).landmarks(),
+            [20, 0, 30, 10]
+        );
+    }
+}
@@ -1,6 +1,14 @@
 pub mod dijkstra;
 pub mod bellman_ford;
 pub mod astar;
+pub mod negative_cycles;
+pub mod spfa;
+pub mod jps;
+pub mod ida_star;
+pub mod weighted_astar;
+pub mod d_star_lite;
+pub mod contraction_hierarchies;
+pub mod landmarks;
 
 //pub use self::shortest_path_tree;
 //pub use self::dijkstra;
@@ -0,0 +1,320 @@
+use crate::algo::cycles::Cycle;
+use crate::algo::errors::AlgoError;
+use crate::graph::edge_traits::{CheckedWeight, Weighted};
+use crate::graph::traits::{DirectedGraph, Graph, GraphIter, GraphMut, KeyedGraph, WithCapacity};
+use crate::graph::types::{DiMapGraph, NodeHashMap};
+
+use ahash::AHashMap;
+use itertools::Itertools;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+// Karp's algorithm. Returns a cycle with the smallest mean edge weight together with its total
+// weight, so the mean is weight / cycle.len(). None if the graph is acyclic. Means are compared by
+// multiplying walk weights by walk lengths, so 2 n^2 times the lightest and the heaviest edge weight
+// have to fit in W, or the result is AlgoError::WeightOverflow
+pub fn minimum_mean_cycle<'a, G, W>(graph: &'a G) -> Result<Option<(Cycle<'a, G>, W)>, AlgoError>
+where
+    G: DirectedGraph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Sub<Output = W> + CheckedWeight + Ord + Default + Clone,
+{
+    let scale = graph
+        .n()
+        .checked_mul(graph.n())
+        .and_then(|k| k.checked_mul(2));
+    let extremes = graph
+        .edges()
+        .map(|edge| edge.data().weight())
+        .minmax()
+        .into_option();
+    if let Some((lightest, heaviest)) = extremes {
+        let fits = |w: &W| scale.is_some_and(|k| w.checked_times(k).is_some());
+        if !fits(&lightest) || !fits(&heaviest) {
+            return Err(AlgoError::WeightOverflow);
+        }
+    }
+
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+    let n = ids.len();
+
+    // dist[k][v] is the lightest walk of exactly k edges ending in v, starting anywhere, and
+    // parent[k][v] the last edge of that walk
+    let mut dist: Vec<Vec<Option<W>>> = vec![vec![Some(W::default()); n]];
+    let mut parent: Vec<Vec<Option<G::EId>>> = vec![vec![None; n]];
+    for k in 1..=n {
+        let mut row: Vec<Option<W>> = vec![None; n];
+        let mut row_parent = vec![None; n];
+        for edge in graph.edges() {
+            let (u, v) = (index[&edge.u()], index[&edge.v()]);
+            if let Some(d) = &dist[k - 1][u] {
+                let candidate = d.clone() + edge.data().weight();
+                if row[v].as_ref().is_none_or(|best| candidate < *best) {
+                    row[v] = Some(candidate);
+                    row_parent[v] = Some(edge.id());
+                }
+            }
+        }
+        dist.push(row);
+        parent.push(row_parent);
+    }
+
+    // min over v of max over k of (dist[n][v] - dist[k][v]) / (n - k)
+    let mut best: Option<(W, usize, usize)> = None;
+    for (v, d_n) in dist[n].iter().enumerate() {
+        let Some(d_n) = d_n else {
+            continue;
+        };
+        let worst = (0..n)
+            .filter_map(|k| {
+                let d_k = dist[k][v].clone()?;
+                Some((d_n.clone() - d_k, n - k))
+            })
+            .max_by(|(a, p), (b, q)| compare_ratios(a, *p, b, *q));
+        if let Some((total, len)) = worst {
+            if best
+                .as_ref()
+                .is_none_or(|(b, q, _)| compare_ratios(&total, len, b, *q).is_lt())
+            {
+                best = Some((total, len, v));
+            }
+        }
+    }
+    let Some((_, _, v)) = best else {
+        return Ok(None);
+    };
+
+    // the n edge walk into v has to repeat a node, and the cycle it closes has the minimum mean
+    let mut step = vec![None; n];
+    let mut walk = Vec::with_capacity(n);
+    let mut cur = v;
+    for k in (1..=n).rev() {
+        step[cur] = Some(k);
+        let edge = graph.edge(parent[k][cur].unwrap()).unwrap();
+        cur = index[&edge.u()];
+        walk.push(edge);
+        if let Some(end) = step[cur] {
+            let mut cycle = walk.split_off(n - end);
+            cycle.reverse();
+            let weight = cycle
+                .iter()
+                .fold(W::default(), |acc, edge| acc + edge.data().weight());
+            return Ok(Some((cycle, weight)));
+        }
+    }
+    unreachable!("a walk of n edges repeats a node")
+}
+
+// Compares a / p with b / q for positive p and q without dividing. a and b are differences of walk
+// weights and p and q at most n, which minimum_mean_cycle made sure fits
+fn compare_ratios<W>(a: &W, p: usize, b: &W, q: usize) -> Ordering
+where
+    W: CheckedWeight + Ord,
+{
+    let scaled = |w: &W, k| {
+        w.checked_times(k)
+            .expect("checked against the edge weights")
+    };
+    scaled(a, q).cmp(&scaled(b, p))
+}
+
+// Returns some negative cycle anywhere in the graph, or None if there is none. Unlike bellman_ford
+// every node is a start, as if a virtual source had a zero weight edge to each of them
+pub fn find_negative_cycle<'a, G, W>(graph: &'a G) -> Option<Cycle<'a, G>>
+where
+    G: Graph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    potentials(graph).err()
+}
+
+// Bellman-Ford from a virtual source connected to every node. Returns the distances, which are all
+// at most zero, or a negative cycle when they don't settle
+fn potentials<'a, G, W>(graph: &'a G) -> Result<NodeHashMap<G, W>, Cycle<'a, G>>
+where
+    G: Graph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    let mut dist = NodeHashMap::<G, W>::default();
+    for node in graph.nodes() {
+        dist.insert(node.id(), W::default());
+    }
+    let mut parent = NodeHashMap::<G, G::EId>::default();
+
+    // n + 1 nodes with the virtual source, so distances settle within n rounds
+    let n = graph.n();
+    let mut last_changed = None;
+    for _ in 0..=n {
+        last_changed = None;
+        for u in graph.nodes() {
+            for (edge, v) in graph.adj(u.id()).unwrap() {
+                let candidate = dist[&u.id()].clone() + edge.data().weight();
+                if candidate < dist[&v.id()] {
+                    dist.insert(v.id(), candidate);
+                    parent.insert(v.id(), edge.id());
+                    last_changed = Some(v.id());
+                }
+            }
+        }
+        if last_changed.is_none() {
+            return Ok(dist);
+        }
+    }
+
+    // walk up n parents to be sure to stand on the cycle and not on a chain hanging off it
+    let mut root = last_changed.unwrap();
+    for _ in 0..n {
+        root = graph.edge(parent[&root]).unwrap().other(root);
+    }
+    let mut cycle = Vec::new();
+    let mut node = root;
+    loop {
+        let edge = graph.edge(parent[&node]).unwrap();
+        node = edge.other(node);
+        cycle.push(edge);
+        if node == root {
+            break;
+        }
+    }
+    cycle.reverse();
+    Err(cycle)
+}
+
+// (i, j, c) stands for x_j - x_i <= c
+pub type Constraint<K, W> = (K, K, W);
+
+// Solves a system of difference constraints. Returns values for every variable
+// that satisfy all constraints, or the constraints along a cycle that contradict each other
+pub fn difference_constraints<K, W>(
+    constraints: impl IntoIterator<Item = Constraint<K, W>>,
+) -> Result<AHashMap<K, W>, Vec<Constraint<K, W>>>
+where
+    K: Eq + Hash + Copy + Debug,
+    W: Weighted<W> + Add<Output = W> + Ord + Default + Clone,
+{
+    let constraints: Vec<_> = constraints.into_iter().collect();
+    let mut graph = DiMapGraph::<K, (), W>::with_capacity(2 * constraints.len(), constraints.len());
+    for (i, j, c) in constraints {
+        graph.put_node(i, ());
+        graph.put_node(j, ());
+        graph.insert_edge(i, j, c).expect("nodes should exist");
+    }
+
+    match potentials(&graph) {
+        Ok(dist) => Ok(dist.into_iter().collect()),
+        Err(cycle) => Err(cycle
+            .into_iter()
+            .map(|edge| (edge.u(), edge.v(), edge.data().clone()))
+            .collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::negative_cycles::{
+        difference_constraints, find_negative_cycle, minimum_mean_cycle,
+    };
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::{DiListGraph, UnListGraph};
+    use itertools::Itertools;
+
+    #[test]
+    fn karp_picks_lowest_mean() {
+        // cycle 0 -> 1 -> 0 has mean 3, cycle 2 -> 3 -> 4 -> 2 has mean 2 and
+        // the self loop on 5 has mean 4
+        let graph = DiListGraph::<(), i32>::from_ordinal(
+            vec![(); 6],
+            vec![
+                (0, 1, 2),
+                (1, 0, 4),
+                (1, 2, -10),
+                (2, 3, 1),
+                (3, 4, 1),
+                (4, 2, 4),
+                (4, 5, 0),
+                (5, 5, 4),
+            ],
+        );
+        let (cycle, weight) = minimum_mean_cycle(&graph).unwrap().unwrap();
+        assert_eq!(weight, 6);
+        assert_eq!(cycle.len(), 3);
+        assert_eq!(
+            cycle.iter().map(|edge| edge.u()).sorted().collect_vec(),
+            vec![2, 3, 4]
+        );
+        for (a, b) in cycle.iter().circular_tuple_windows() {
+            assert_eq!(a.v(), b.u());
+        }
+
+        let acyclic = DiListGraph::<(), i32>::from_ordinal(vec![(); 3], vec![(0, 1, 1), (1, 2, 1)]);
+        assert_eq!(minimum_mean_cycle(&acyclic).unwrap().map(|(_, w)| w), None);
+    }
+
+    #[test]
+    fn karp_rejects_weights_that_overflow() {
+        // walk weights times walk lengths need up to 2 * 3^2 * 2 * 10^8, more than an i32 holds
+        let edges = vec![(0, 1, 200_000_000), (1, 2, -200_000_000), (2, 0, 5)];
+        let graph = DiListGraph::<(), i32>::from_ordinal(vec![(); 3], edges.clone());
+        assert_eq!(
+            minimum_mean_cycle(&graph).map(|cycle| cycle.is_some()),
+            Err(AlgoError::WeightOverflow)
+        );
+
+        let graph = DiListGraph::<(), i64>::from_ordinal(
+            vec![(); 3],
+            edges
+                .into_iter()
+                .map(|(u, v, w)| (u, v, w as i64))
+                .collect(),
+        );
+        let (cycle, weight) = minimum_mean_cycle(&graph).unwrap().unwrap();
+        assert_eq!((cycle.len(), weight), (3, 5));
+    }
+
+    #[test]
+    fn negative_cycle_unreachable_from_first_node() {
+        // 0 -> 1 is harmless, 2 -> 3 -> 4 -> 2 weighs -1 and can't be reached from 0
+        let graph = DiListGraph::<(), i32>::from_ordinal(
+            vec![(); 5],
+            vec![(0, 1, 3), (2, 3, 2), (3, 4, -4), (4, 2, 1), (1, 2, 0)],
+        );
+        let cycle = find_negative_cycle(&graph).unwrap();
+        assert_eq!(cycle.iter().map(|edge| edge.data()).sum::<i32>(), -1);
+        assert_eq!(
+            cycle.iter().map(|edge| edge.u()).sorted().collect_vec(),
+            vec![2, 3, 4]
+        );
+
+        let graph = UnListGraph::<(), i32>::from_ordinal(vec![(); 3], vec![(0, 1, 1), (1, 2, 2)]);
+        assert!(find_negative_cycle(&graph).is_none());
+    }
+
+    #[test]
+    fn solve_difference_constraints() {
+        let constraints = vec![("a", "b", 3), ("b", "c", -2), ("a", "c", 0), ("c", "a", 4)];
+        let x = difference_constraints(constraints.clone()).unwrap();
+        for (i, j, c) in constraints {
+            assert!(x[j] - x[i] <= c);
+        }
+
+        // b - a <= 1, c - b <= 1 and a - c <= -3 add up to 0 <= -1
+        let cycle = difference_constraints(vec![
+            ("a", "b", 1),
+            ("b", "c", 1),
+            ("c", "a", -3),
+            ("a", "d", 0),
+        ])
+        .unwrap_err();
+        assert_eq!(cycle.iter().map(|(_, _, c)| c).sum::<i32>(), -1);
+        assert_eq!(cycle.len(), 3);
+    }
+}
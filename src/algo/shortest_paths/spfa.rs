@@ -0,0 +1,316 @@
+use crate::algo::cycles::Cycle;
+use crate::algo::errors::AlgoError;
+use crate::graph::edge_traits::{CheckedWeight, Weighted};
+use crate::graph::traits::{Graph, GraphIter};
+use crate::graph::types::NodeHashMap;
+use crate::iter::traits::WeightedPathTree;
+
+use std::ops::Add;
+
+pub type ShortestPaths<'a, G, W> = (WeightedPathTree<'a, G, W>, Option<Cycle<'a, G>>);
+
+// Shortest path faster algorithm: Bellman-Ford that only scans edges out of nodes whose distance
+// changed in the previous round. Same results as bellman_ford
+pub fn spfa<'a, G, W>(graph: &'a G, start: G::NId) -> Result<ShortestPaths<'a, G, W>, AlgoError>
+where
+    G: Graph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + CheckedWeight + Ord + Default + Clone,
+{
+    spfa_limited(graph, start, None, None)
+}
+
+// SPFA that only considers walks of at most max_hops edges, and that stops expanding nodes which
+// can't lead to a lighter path to target anymore. With a target the tree holds just the path to it.
+// Without one, a hop limit keeps every weight exact, but a node's parent edge is the one of its
+// lightest walk, so path_to can go through nodes that were reached more cheaply with more hops
+pub fn spfa_limited<'a, G, W>(
+    graph: &'a G,
+    start: G::NId,
+    target: Option<G::NId>,
+    max_hops: Option<usize>,
+) -> Result<ShortestPaths<'a, G, W>, AlgoError>
+where
+    G: Graph + GraphIter,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + CheckedWeight + Ord + Default + Clone,
+{
+    if !graph.contains_node(start) {
+        return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+    }
+    if let Some(target) = target.filter(|&t| !graph.contains_node(t)) {
+        return Err(AlgoError::TargetNodeNotFound(format!("{:?}", target)));
+    }
+
+    // nodes can only be skipped when the rest of a path can't get lighter than some bound
+    let lightest_edge = target.and_then(|_| {
+        graph
+            .edges()
+            .map(|edge| edge.data().weight())
+            .min()
+            .filter(|w| *w >= W::default() || max_hops.is_some())
+            .map(|w| w.min(W::default()))
+    });
+
+    // only the hop limited path to a target needs to know what a node weighed in earlier rounds
+    let n = graph.n();
+    let mut search = Search::new(graph, start, target.is_some() && max_hops.is_some());
+    let mut frontier = vec![(start, W::default())];
+    let mut round = 0;
+    let mut cycle = None;
+    while !frontier.is_empty() && max_hops.is_none_or(|k| round < k) {
+        round += 1;
+        let mut changed = NodeHashMap::<G, W>::default();
+        for (u, u_dist) in frontier {
+            if let (Some(t), Some(w)) = (target, &lightest_edge) {
+                // a bound that doesn't fit in W can't be used to prune
+                let hops_left = max_hops.map_or(1, |k| k - round + 1);
+                let bound = w
+                    .checked_times(hops_left)
+                    .and_then(|rest| u_dist.checked_plus(&rest));
+                if bound.is_some_and(|bound| search.dist.get(&t).is_some_and(|best| bound >= *best))
+                {
+                    continue;
+                }
+            }
+            for (edge, v) in graph.adj(u).unwrap() {
+                let candidate = u_dist.clone() + edge.data().weight();
+                if search.relax(v.id(), edge.id(), candidate.clone(), round) {
+                    changed.insert(v.id(), candidate);
+                }
+            }
+        }
+
+        // a walk of n edges that is still getting lighter goes around a negative cycle
+        if round >= n {
+            if let Some(v) = changed.keys().next() {
+                cycle = Some(search.negative_cycle(*v));
+                break;
+            }
+        }
+        frontier = changed.into_iter().collect();
+    }
+
+    let mut tree = WeightedPathTree::new(graph);
+    match target {
+        Some(t) => {
+            tree.insert_node(start, None, W::default());
+            if cycle.is_none() {
+                for (id, parent, weight) in search.walk(t, round) {
+                    tree.insert_node(id, parent, weight);
+                }
+            }
+        }
+        None => {
+            for (id, weight) in search.dist {
+                tree.insert_node(id, search.parent.get(&id).copied(), weight);
+            }
+        }
+    }
+    Ok((tree, cycle))
+}
+
+// (round, edge, weight) for every improvement of a node's distance
+type History<G, W> = Vec<(usize, Option<<G as Graph>::EId>, W)>;
+
+struct Search<'a, G, W>
+where
+    G: Graph,
+{
+    graph: &'a G,
+    dist: NodeHashMap<G, W>,
+    parent: NodeHashMap<G, G::EId>,
+    history: Option<NodeHashMap<G, History<G, W>>>,
+}
+
+impl<'a, G, W> Search<'a, G, W>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + Ord + Default + Clone,
+{
+    fn new(graph: &'a G, start: G::NId, keep_history: bool) -> Self {
+        let mut search = Search {
+            graph,
+            dist: NodeHashMap::<G, W>::default(),
+            parent: NodeHashMap::<G, G::EId>::default(),
+            history: keep_history.then(NodeHashMap::<G, History<G, W>>::default),
+        };
+        search.dist.insert(start, W::default());
+        if let Some(history) = &mut search.history {
+            history.insert(start, vec![(0, None, W::default())]);
+        }
+        search
+    }
+
+    fn relax(&mut self, v: G::NId, edge: G::EId, candidate: W, round: usize) -> bool {
+        if self.dist.get(&v).is_some_and(|best| candidate >= *best) {
+            return false;
+        }
+        if let Some(history) = &mut self.history {
+            history
+                .entry(v)
+                .or_default()
+                .push((round, Some(edge), candidate.clone()));
+        }
+        self.dist.insert(v, candidate);
+        self.parent.insert(v, edge);
+        true
+    }
+
+    // The walk whose weight v had at the end of the given round, as (node, parent edge, weight)
+    // from the start onwards. Every step is taken from the round before, so it has at most round
+    // edges. Without history it is the path along the parent edges, which is the lightest one once
+    // the distances settled
+    fn walk(&self, v: G::NId, round: usize) -> Vec<(G::NId, Option<G::EId>, W)> {
+        let Some(history) = &self.history else {
+            let mut walk = Vec::new();
+            let mut v = v;
+            while let Some(weight) = self.dist.get(&v) {
+                let edge = self.parent.get(&v).copied();
+                walk.push((v, edge, weight.clone()));
+                match edge {
+                    Some(edge) => v = self.graph.edge(edge).unwrap().other(v),
+                    None => break,
+                }
+            }
+            walk.reverse();
+            return walk;
+        };
+
+        let mut walk = Vec::new();
+        let (mut v, mut round) = (v, round);
+        while let Some((at, edge, weight)) = history
+            .get(&v)
+            .and_then(|history| history.iter().rev().find(|(at, _, _)| *at <= round))
+        {
+            walk.push((v, *edge, weight.clone()));
+            match edge {
+                Some(edge) => {
+                    v = self.graph.edge(*edge).unwrap().other(v);
+                    round = at - 1;
+                }
+                None => break,
+            }
+        }
+        walk.reverse();
+        walk
+    }
+
+    // v still got lighter after n rounds, so following the parent edges back from it can't end at
+    // the start and runs into a cycle, which like every cycle of parent edges is negative
+    fn negative_cycle(&self, v: G::NId) -> Cycle<'a, G> {
+        let mut position = NodeHashMap::<G, usize>::default();
+        let mut edges = Vec::new();
+        let mut v = v;
+        while !position.contains_key(&v) {
+            position.insert(v, edges.len());
+            let edge = self.graph.edge(self.parent[&v]).unwrap();
+            v = edge.other(v);
+            edges.push(edge);
+        }
+        let mut cycle = edges.split_off(position[&v]);
+        cycle.reverse();
+        cycle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::bellman_ford::bellman_ford;
+    use crate::algo::shortest_paths::spfa::{spfa, spfa_limited};
+    use crate::graph::traits::{GraphMut, KeyedGraph, OrdinalGraph, WithCapacity};
+    use crate::graph::types::{DiListGraph, DiMapGraph};
+    use crate::iter::traits::Tree;
+
+    #[test]
+    fn spfa_matches_bellman_ford() {
+        let graph = DiListGraph::<(), i32>::from_ordinal(
+            vec![(); 6],
+            vec![
+                (0, 1, 4),
+                (0, 2, 1),
+                (2, 1, 2),
+                (1, 3, -2),
+                (2, 3, 5),
+                (3, 4, 3),
+                (4, 2, 1),
+            ],
+        );
+        let (tree, cycle) = spfa(&graph, 0).unwrap();
+        let (expected, _) = bellman_ford(&graph, 0).unwrap();
+        assert!(cycle.is_none());
+        for v in 0..6 {
+            assert_eq!(tree.weight(v), expected.weight(v));
+        }
+        let path = tree.path_to(4).unwrap();
+        assert_eq!(
+            path.nodes().map(|node| node.id()).collect::<Vec<_>>(),
+            vec![0, 2, 1, 3, 4]
+        );
+
+        assert!(matches!(
+            spfa(&graph, 9),
+            Err(AlgoError::StartNodeNotFound(..))
+        ));
+        assert!(matches!(
+            spfa_limited(&graph, 0, Some(9), None),
+            Err(AlgoError::TargetNodeNotFound(..))
+        ));
+    }
+
+    #[test]
+    fn spfa_negative_cycle() {
+        // 1 -> 2 -> 3 -> 1 weighs -1, 4 hangs off the cycle
+        let graph = DiListGraph::<(), i32>::from_ordinal(
+            vec![(); 5],
+            vec![(0, 1, 1), (1, 2, 2), (2, 3, -4), (3, 1, 1), (3, 4, 1)],
+        );
+        let (_, cycle) = spfa(&graph, 0).unwrap();
+        let cycle = cycle.unwrap();
+        assert_eq!(cycle.iter().map(|edge| edge.data()).sum::<i32>(), -1);
+        let mut nodes: Vec<_> = cycle.iter().map(|edge| edge.u()).collect();
+        nodes.sort();
+        assert_eq!(nodes, vec![1, 2, 3]);
+
+        // hop limited searches for a target find it as well once the limit allows n rounds
+        let (_, cycle) = spfa_limited(&graph, 0, Some(4), Some(20)).unwrap();
+        assert_eq!(
+            cycle.unwrap().iter().map(|edge| edge.data()).sum::<i32>(),
+            -1
+        );
+    }
+
+    #[test]
+    fn at_most_k_flights() {
+        let mut graph = DiMapGraph::with_capacity(4, 5);
+        for city in ["AMS", "BER", "CDG", "DUB"] {
+            graph.put_node(city, ());
+        }
+        graph.insert_edge("AMS", "DUB", 500).unwrap();
+        graph.insert_edge("AMS", "BER", 100).unwrap();
+        graph.insert_edge("BER", "DUB", 300).unwrap();
+        graph.insert_edge("BER", "CDG", 50).unwrap();
+        graph.insert_edge("CDG", "DUB", 50).unwrap();
+
+        let route = |hops| {
+            let (tree, _) = spfa_limited(&graph, "AMS", Some("DUB"), Some(hops)).unwrap();
+            let path = tree
+                .path_to("DUB")
+                .map(|path| path.nodes().map(|node| node.id()).collect::<Vec<_>>());
+            (tree.weight("DUB").copied(), path)
+        };
+        assert_eq!(route(1), (Some(500), Some(vec!["AMS", "DUB"])));
+        assert_eq!(route(2), (Some(400), Some(vec!["AMS", "BER", "DUB"])));
+        assert_eq!(
+            route(3),
+            (Some(200), Some(vec!["AMS", "BER", "CDG", "DUB"]))
+        );
+        assert_eq!(route(0), (None, None));
+
+        // without a hop limit the pruned search still finds the cheapest route
+        let (tree, _) = spfa_limited(&graph, "AMS", Some("DUB"), None).unwrap();
+        assert_eq!(tree.weight("DUB"), Some(&200));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::algo::errors::AlgoError;
+use crate::algo::shortest_paths::astar::Weight;
+use crate::graph::edge_traits::{CheckedWeight, Weighted};
+use crate::graph::node::Node;
+use crate::graph::traits::Graph;
+use crate::graph::types::NodeHashMap;
+use crate::iter::pfs::{pfs, PriorityType};
+use crate::iter::traits::{Path, Tree};
+
+use dary_heap::DaryHeap;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ops::Add;
+
+// Factor numerator / denominator the heuristic gets multiplied by, at least 1
+pub type Inflation = (usize, usize);
+
+// A* with the heuristic inflated by the given factor, which makes it head for the goal more
+// greedily. Every node is expanded at most once and with a consistent heuristic the path is at most
+// the factor times longer than a shortest one. Priorities are distance times the denominator plus
+// heuristic times the numerator, AlgoError::WeightOverflow if one of them doesn't fit in W. An
+// inflation below 1 or with a zero denominator is AlgoError::InvalidInflation
+pub fn weighted_astar<'a, G, W>(
+    graph: &'a G,
+    start: G::NId,
+    goal: G::NId,
+    heuristic: impl Fn(&Node<'a, G::NId, G::N>) -> W,
+    inflation: Inflation,
+) -> Result<(Path<'a, G>, W), AlgoError>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + CheckedWeight + Ord + Default + Clone,
+{
+    if !graph.contains_node(start) {
+        return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+    }
+    if !graph.contains_node(goal) {
+        return Err(AlgoError::TargetNodeNotFound(format!("{:?}", goal)));
+    }
+    check_inflation(inflation)?;
+
+    // priorities are scaled by the denominator to stay whole
+    let overflow = &Cell::new(false);
+    let mut search = pfs(
+        graph,
+        start,
+        Weight {
+            distance: W::default(),
+            priority: W::default(),
+        },
+        PriorityType::Min,
+        move |acc: Weight<W>, edge, node| {
+            let distance = acc.distance + edge.data().weight();
+            let priority = priority(&distance, &heuristic(node), inflation).unwrap_or_else(|| {
+                overflow.set(true);
+                distance.clone()
+            });
+            Weight { distance, priority }
+        },
+    );
+    let found = search.find(|(_, node, _)| node.id() == goal);
+    if overflow.get() {
+        return Err(AlgoError::WeightOverflow);
+    }
+    match found {
+        Some((_, _, weight)) => Ok((search.path_to(goal).unwrap(), weight.distance)),
+        None => Err(AlgoError::NoPathFromStartToEnd(
+            format!("{:?}", start),
+            format!("{:?}", goal),
+        )),
+    }
+}
+
+fn check_inflation((num, den): Inflation) -> Result<(), AlgoError> {
+    if den == 0 || num < den {
+        return Err(AlgoError::InvalidInflation(format!("{}/{}", num, den)));
+    }
+    Ok(())
+}
+
+// Distance times den plus heuristic times num, None if that doesn't fit in W
+fn priority<W>(distance: &W, heuristic: &W, (num, den): Inflation) -> Option<W>
+where
+    W: CheckedWeight,
+{
+    distance
+        .checked_times(den)?
+        .checked_plus(&heuristic.checked_times(num)?)
+}
+
+// Anytime repairing A* (ARA*). Runs weighted A* once for every inflation of the schedule, which
+// should be decreasing, and yields a path to the goal with its length and the inflation it was
+// found with whenever it is shorter than the one before. Instead of starting over, every run picks
+// up the open nodes of the previous one and only expands again the nodes whose distance improved
+// after they were expanded. A path found with inflation 1 is a shortest one. A priority that
+// doesn't fit in W ends the search with AlgoError::WeightOverflow. Every inflation of the schedule
+// is checked up front, as in weighted_astar
+pub fn anytime_astar<'a, G, W, H>(
+    graph: &'a G,
+    start: G::NId,
+    goal: G::NId,
+    heuristic: H,
+    schedule: impl IntoIterator<Item = Inflation>,
+) -> Result<AnytimeAstar<'a, G, W, H>, AlgoError>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + CheckedWeight + Ord + Default + Clone,
+    H: Fn(&Node<'a, G::NId, G::N>) -> W,
+{
+    let Some(node) = graph.node(start) else {
+        return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+    };
+    if !graph.contains_node(goal) {
+        return Err(AlgoError::TargetNodeNotFound(format!("{:?}", goal)));
+    }
+    let schedule: Vec<_> = schedule.into_iter().collect();
+    for &inflation in &schedule {
+        check_inflation(inflation)?;
+    }
+
+    let mut search = AnytimeAstar {
+        graph,
+        goal,
+        schedule: schedule.into_iter(),
+        distance: NodeHashMap::<G, W>::default(),
+        estimate: NodeHashMap::<G, W>::default(),
+        parent: NodeHashMap::<G, G::EId>::default(),
+        open: NodeHashMap::<G, W>::default(),
+        closed: NodeHashMap::<G, ()>::default(),
+        inconsistent: vec![start],
+        heap: DaryHeap::new(),
+        best: None,
+        heuristic,
+    };
+    search.estimate.insert(start, (search.heuristic)(&node));
+    search.distance.insert(start, W::default());
+    Ok(search)
+}
+
+pub struct AnytimeAstar<'a, G, W, H>
+where
+    G: Graph,
+{
+    graph: &'a G,
+    goal: G::NId,
+    schedule: std::vec::IntoIter<Inflation>,
+    heuristic: H,
+    distance: NodeHashMap<G, W>,
+    estimate: NodeHashMap<G, W>,
+    parent: NodeHashMap<G, G::EId>,
+    // key of every open node, heap entries with another key are stale
+    open: NodeHashMap<G, W>,
+    closed: NodeHashMap<G, ()>,
+    // nodes that improved after being expanded in the current run, to be opened in the next one
+    inconsistent: Vec<G::NId>,
+    heap: DaryHeap<Open<G::NId, W>, 4>,
+    best: Option<W>,
+}
+
+impl<'a, G, W, H> AnytimeAstar<'a, G, W, H>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + CheckedWeight + Ord + Default + Clone,
+    H: Fn(&Node<'a, G::NId, G::N>) -> W,
+{
+    fn key(&self, id: G::NId, inflation: Inflation) -> Result<W, AlgoError> {
+        priority(&self.distance[&id], &self.estimate[&id], inflation)
+            .ok_or(AlgoError::WeightOverflow)
+    }
+
+    fn push(&mut self, id: G::NId, inflation: Inflation) -> Result<(), AlgoError> {
+        let key = self.key(id, inflation)?;
+        self.open.insert(id, key.clone());
+        self.heap.push(Open { key, id });
+        Ok(())
+    }
+
+    // Expands nodes by key until none of the open ones has a smaller key than the goal
+    fn improve_path(&mut self, inflation: Inflation) -> Result<(), AlgoError> {
+        let graph = self.graph;
+        let reopen: Vec<G::NId> = self
+            .open
+            .keys()
+            .copied()
+            .chain(self.inconsistent.drain(..))
+            .collect();
+        self.open.clear();
+        self.closed.clear();
+        self.heap.clear();
+        for id in reopen {
+            self.push(id, inflation)?;
+        }
+
+        while let Some(top) = self.heap.peek() {
+            if self.open.get(&top.id) != Some(&top.key) {
+                self.heap.pop();
+                continue;
+            }
+            if self.distance.contains_key(&self.goal) && self.key(self.goal, inflation)? <= top.key
+            {
+                break;
+            }
+            let u = self.heap.pop().unwrap().id;
+            self.open.remove(&u);
+            self.closed.insert(u, ());
+
+            for (edge, node) in graph.adj(u).unwrap() {
+                let v = node.id();
+                let candidate = self.distance[&u].clone() + edge.data().weight();
+                if self.distance.get(&v).is_some_and(|d| candidate >= *d) {
+                    continue;
+                }
+                self.distance.insert(v, candidate);
+                self.parent.insert(v, edge.id());
+                if !self.estimate.contains_key(&v) {
+                    self.estimate.insert(v, (self.heuristic)(&node));
+                }
+                if self.closed.contains_key(&v) {
+                    self.inconsistent.push(v);
+                } else {
+                    self.push(v, inflation)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Follows the parents back from the goal. Distances of nodes on the way may have improved
+    // since their children were reached, so the length is summed up again
+    fn path_to_goal(&self) -> (Path<'a, G>, W) {
+        let mut steps = Vec::new();
+        let mut length = W::default();
+        let mut id = self.goal;
+        while let Some(&parent) = self.parent.get(&id) {
+            let edge = self.graph.edge(parent).unwrap();
+            length = length + edge.data().weight();
+            let prev = edge.other(id);
+            steps.push((Some(edge), self.graph.node(id).unwrap()));
+            id = prev;
+        }
+        steps.push((None, self.graph.node(id).unwrap()));
+        steps.reverse();
+        (Path::new(steps), length)
+    }
+}
+
+impl<'a, G, W, H> Iterator for AnytimeAstar<'a, G, W, H>
+where
+    G: Graph,
+    G::E: Weighted<W>,
+    W: Add<Output = W> + CheckedWeight + Ord + Default + Clone,
+    H: Fn(&Node<'a, G::NId, G::N>) -> W,
+{
+    type Item = Result<(Path<'a, G>, W, Inflation), AlgoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(inflation) = self.schedule.next() {
+            if let Err(error) = self.improve_path(inflation) {
+                self.schedule = Vec::new().into_iter();
+                return Some(Err(error));
+            }
+            if !self.distance.contains_key(&self.goal) {
+                continue;
+            }
+            let (path, length) = self.path_to_goal();
+            if self.best.as_ref().is_none_or(|best| length < *best) {
+                self.best = Some(length.clone());
+                return Some(Ok((path, length, inflation)));
+            }
+        }
+        None
+    }
+}
+
+#[derive(PartialEq, Eq)]
+pub(crate) struct Open<NId, W> {
+    pub(crate) key: W,
+    pub(crate) id: NId,
+}
+
+// Smallest key first
+impl<NId: Eq, W: Ord> Ord for Open<NId, W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.cmp(&self.key)
+    }
+}
+
+impl<NId: Eq, W: Ord> PartialOrd for Open<NId, W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::algo::shortest_paths::weighted_astar::{anytime_astar, weighted_astar};
+    use crate::graph::grid::Position;
+    use crate::graph::grid_map::GridMap;
+    use crate::graph::node::Node;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::DiListGraph;
+    use crate::iter::traits::WeightedPathTree;
+    use itertools::Itertools;
+
+    // Straight to the goal through a swamp of 5s, or the long way round below the wall
+    const MAP: &str = "\
+S.5555555.E
+.#########.
+.#.......#.
+.#.#####.#.
+...#...#...";
+
+    #[test]
+    fn weighted_astar_is_bounded() {
+        let map = GridMap::parse(MAP).unwrap();
+        let (start, goal) = (map.special('S').unwrap(), map.special('E').unwrap());
+        let h = |node: &Node<Position, char>| node.id().manhattan(goal) as u32;
+        let tree = WeightedPathTree::from(dijkstra(map.graph(), start).unwrap());
+        let shortest = *tree.weight(goal).unwrap();
+
+        for (num, den) in [(1, 1), (3, 2), (2, 1), (5, 1)] {
+            let (path, length) = weighted_astar(map.graph(), start, goal, h, (num, den)).unwrap();
+            assert!(length >= shortest && length as usize * den <= shortest as usize * num);
+            let walked: u32 = path.edges().map(|edge| *edge.data()).sum();
+            assert_eq!(walked, length);
+        }
+        let (_, length) = weighted_astar(map.graph(), start, goal, h, (1, 1)).unwrap();
+        assert_eq!(length, shortest);
+
+        assert!(matches!(
+            weighted_astar(map.graph(), start, Position::from((1, 1)), h, (2, 1)),
+            Err(AlgoError::TargetNodeNotFound(..))
+        ));
+
+        for inflation in [(1, 0), (1, 2), (0, 0)] {
+            assert!(matches!(
+                weighted_astar(map.graph(), start, goal, h, inflation),
+                Err(AlgoError::InvalidInflation(..))
+            ));
+        }
+        assert_eq!(
+            anytime_astar(map.graph(), start, goal, h, [(3, 1), (1, 0)]).err(),
+            Some(AlgoError::InvalidInflation("1/0".to_string()))
+        );
+    }
+
+    #[test]
+    fn inflated_priority_overflows() {
+        // a heuristic of 100 inflated 3 times doesn't fit in a u8
+        let graph =
+            DiListGraph::<(), u8>::from_ordinal(vec![(); 3], vec![(0, 1, 100), (1, 2, 100)]);
+        let h = |node: &Node<usize, ()>| if node.id() == 2 { 0 } else { 100 };
+        assert_eq!(
+            weighted_astar(&graph, 0, 2, h, (3, 1)).map(|(_, length)| length),
+            Err(AlgoError::WeightOverflow)
+        );
+        assert_eq!(
+            weighted_astar(&graph, 0, 2, h, (1, 1)).map(|(_, length)| length),
+            Ok(200)
+        );
+
+        let mut search = anytime_astar(&graph, 0, 2, h, [(3, 1), (1, 1)]).unwrap();
+        assert!(matches!(
+            search.next(),
+            Some(Err(AlgoError::WeightOverflow))
+        ));
+        assert!(search.next().is_none());
+    }
+
+    #[test]
+    fn anytime_astar_improves() {
+        let map = GridMap::parse(MAP).unwrap();
+        let (start, goal) = (map.special('S').unwrap(), map.special('E').unwrap());
+        let h = |node: &Node<Position, char>| node.id().manhattan(goal) as u32;
+        let tree = WeightedPathTree::from(dijkstra(map.graph(), start).unwrap());
+        let shortest = *tree.weight(goal).unwrap();
+
+        let schedule = [(5, 1), (3, 1), (2, 1), (3, 2), (1, 1)];
+        let solutions: Vec<_> = anytime_astar(map.graph(), start, goal, h, schedule)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(solutions.len() > 1);
+        for ((_, a, _), (_, b, _)) in solutions.iter().tuple_windows() {
+            assert!(b < a);
+        }
+        for (path, length, (num, den)) in &solutions {
+            assert!(*length as usize * den <= shortest as usize * num);
+            let walked: u32 = path.edges().map(|edge| *edge.data()).sum();
+            assert_eq!(walked, *length);
+            let ends = (
+                path.nodes().next().unwrap().id(),
+                path.nodes().last().unwrap().id(),
+            );
+            assert_eq!(ends, (start, goal));
+        }
+        // the greedy first run goes through the swamp, the detour is found once inflation drops
+        assert_eq!(solutions[0].2, (5, 1));
+        assert_eq!(solutions.last().unwrap().1, shortest);
+    }
+}
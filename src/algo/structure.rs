@@ -0,0 +1,308 @@
+use crate::graph::traits::{GraphIter, KeyedGraph, UndirectedGraph, WithCapacity};
+use crate::graph::types::NodeHashMap;
+
+// Returns map of node ids to the number of triangles each node belongs to
+pub fn triangles<G>(graph: &G) -> NodeHashMap<G, usize>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let SimpleAdjacency { ids, adj, .. } = simple_adjacency(graph);
+    let counts = triangle_counts(&adj);
+    ids.into_iter().zip(counts).collect()
+}
+
+// Fraction of pairs of neighbors of each node that are adjacent themselves, 0 for nodes with
+// fewer than two neighbors
+pub fn clustering<G>(graph: &G) -> NodeHashMap<G, f64>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let SimpleAdjacency { ids, adj, .. } = simple_adjacency(graph);
+    let counts = triangle_counts(&adj);
+    ids.into_iter()
+        .zip(counts)
+        .zip(adj.iter())
+        .map(|((id, t), neighbors)| (id, local_clustering(t, neighbors.len())))
+        .collect()
+}
+
+pub fn average_clustering<G>(graph: &G) -> f64
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let coefficients = clustering(graph);
+    if coefficients.is_empty() {
+        return 0.0;
+    }
+    coefficients.values().sum::<f64>() / coefficients.len() as f64
+}
+
+// Global clustering coefficient: 3 * triangles / connected triples
+pub fn transitivity<G>(graph: &G) -> f64
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let SimpleAdjacency { adj, .. } = simple_adjacency(graph);
+    let closed: usize = triangle_counts(&adj).into_iter().sum();
+    let triples: usize = adj
+        .iter()
+        .map(|neighbors| neighbors.len() * neighbors.len().saturating_sub(1) / 2)
+        .sum();
+    if triples == 0 {
+        return 0.0;
+    }
+    closed as f64 / triples as f64
+}
+
+// Batagelj-Zaversnik O(n + m) core decomposition. The core number of a node is the largest k such
+// that the node belongs to a subgraph where every node has at least k neighbors
+pub fn core_number<G>(graph: &G) -> NodeHashMap<G, usize>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let SimpleAdjacency { ids, adj, .. } = simple_adjacency(graph);
+    let n = ids.len();
+    let mut degree: Vec<usize> = adj.iter().map(|neighbors| neighbors.len()).collect();
+    let max_degree = degree.iter().copied().max().unwrap_or(0);
+
+    // bucket sort nodes by degree. bin[d] is the start of the nodes with degree d in vert
+    let mut bin = vec![0; max_degree + 1];
+    for &d in &degree {
+        bin[d] += 1;
+    }
+    let mut start = 0;
+    for count in bin.iter_mut() {
+        let size = *count;
+        *count = start;
+        start += size;
+    }
+    let mut vert = vec![0; n];
+    let mut pos = vec![0; n];
+    for v in 0..n {
+        pos[v] = bin[degree[v]];
+        vert[pos[v]] = v;
+        bin[degree[v]] += 1;
+    }
+    for d in (1..=max_degree).rev() {
+        bin[d] = bin[d - 1];
+    }
+    bin[0] = 0;
+
+    // process nodes in increasing degree order, moving each higher degree neighbor down a bucket
+    for i in 0..n {
+        let v = vert[i];
+        for &u in &adj[v] {
+            if degree[u] > degree[v] {
+                let du = degree[u];
+                let pu = pos[u];
+                let pw = bin[du];
+                let w = vert[pw];
+                if u != w {
+                    vert.swap(pu, pw);
+                    pos[u] = pw;
+                    pos[w] = pu;
+                }
+                bin[du] += 1;
+                degree[u] -= 1;
+            }
+        }
+    }
+
+    ids.into_iter().zip(degree).collect()
+}
+
+// Induced subgraph on the nodes with core number at least k, keeping the original node ids
+pub fn k_core<G, H>(graph: &G, k: usize) -> H
+where
+    G: UndirectedGraph + GraphIter,
+    G::N: Clone,
+    G::E: Clone,
+    H: KeyedGraph<NId = G::NId, N = G::N, E = G::E> + WithCapacity,
+{
+    let cores = core_number(graph);
+    let nodes: Vec<_> = graph
+        .nodes()
+        .filter(|node| cores[&node.id()] >= k)
+        .map(|node| (node.id(), node.data().clone()))
+        .collect();
+    let edges: Vec<_> = graph
+        .edges()
+        .filter(|edge| cores[&edge.u()] >= k && cores[&edge.v()] >= k)
+        .map(|edge| (edge.u(), edge.v(), edge.data().clone()))
+        .collect();
+    H::from_keyed(nodes, edges)
+}
+
+// Nodes indexed 0..n in iteration order, where each adjacency list is sorted, deduplicated and
+// free of self loops
+pub(crate) struct SimpleAdjacency<G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    pub ids: Vec<G::NId>,
+    pub index: NodeHashMap<G, usize>,
+    pub adj: Vec<Vec<usize>>,
+}
+
+pub(crate) fn simple_adjacency<G>(graph: &G) -> SimpleAdjacency<G>
+where
+    G: UndirectedGraph + GraphIter,
+{
+    let ids: Vec<G::NId> = graph.nodes().map(|node| node.id()).collect();
+    let mut index = NodeHashMap::<G, usize>::default();
+    for (i, &id) in ids.iter().enumerate() {
+        index.insert(id, i);
+    }
+
+    let adj = ids
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| {
+            let mut neighbors: Vec<usize> = graph
+                .adj(id)
+                .unwrap()
+                .map(|(_, node)| index[&node.id()])
+                .filter(|&j| j != i)
+                .collect();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            neighbors
+        })
+        .collect();
+
+    SimpleAdjacency { ids, index, adj }
+}
+
+// Orients every edge from lower to higher (degree, index) rank so each triangle is found exactly
+// once from its lowest ranked node
+fn triangle_counts(adj: &[Vec<usize>]) -> Vec<usize> {
+    let rank = |v: usize| (adj[v].len(), v);
+    let higher: Vec<Vec<usize>> = adj
+        .iter()
+        .enumerate()
+        .map(|(u, neighbors)| {
+            neighbors
+                .iter()
+                .copied()
+                .filter(|&v| rank(v) > rank(u))
+                .collect()
+        })
+        .collect();
+
+    let mut counts = vec![0; adj.len()];
+    let mut marked = vec![false; adj.len()];
+    for u in 0..adj.len() {
+        for &v in &higher[u] {
+            marked[v] = true;
+        }
+        for &v in &higher[u] {
+            for &w in &higher[v] {
+                if marked[w] {
+                    counts[u] += 1;
+                    counts[v] += 1;
+                    counts[w] += 1;
+                }
+            }
+        }
+        for &v in &higher[u] {
+            marked[v] = false;
+        }
+    }
+    counts
+}
+
+fn local_clustering(triangles: usize, degree: usize) -> f64 {
+    if degree < 2 {
+        return 0.0;
+    }
+    2.0 * triangles as f64 / (degree * (degree - 1)) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::structure::{
+        average_clustering, clustering, core_number, k_core, transitivity, triangles,
+    };
+    use crate::graph::traits::{Graph, GraphIter, OrdinalGraph};
+    use crate::graph::types::{UnListGraph, UnMapGraph};
+
+    // 0 --- 1
+    // | \   |
+    // |  \  |
+    // 3 --- 2 --- 4 --- 5
+    fn diamond_with_tail() -> UnListGraph<(), ()> {
+        UnListGraph::builder()
+            .with_size(6)
+            .edge(0, 1, ())
+            .edge(1, 2, ())
+            .edge(0, 2, ())
+            .edge(0, 3, ())
+            .edge(3, 2, ())
+            .edge(2, 4, ())
+            .edge(4, 5, ())
+            .build()
+    }
+
+    #[test]
+    fn triangle_counts() {
+        let graph = diamond_with_tail();
+        let counts = triangles(&graph);
+        assert_eq!(counts[&0], 2);
+        assert_eq!(counts[&1], 1);
+        assert_eq!(counts[&2], 2);
+        assert_eq!(counts[&3], 1);
+        assert_eq!(counts[&4], 0);
+        assert_eq!(counts[&5], 0);
+    }
+
+    #[test]
+    fn clustering_coefficients() {
+        let graph = diamond_with_tail();
+        let c = clustering(&graph);
+        assert_eq!(c[&0], 2.0 / 3.0);
+        assert_eq!(c[&1], 1.0);
+        assert_eq!(c[&2], 2.0 / 6.0);
+        assert_eq!(c[&4], 0.0);
+        assert_eq!(c[&5], 0.0);
+
+        let expected_average = (2.0 / 3.0 + 1.0 + 2.0 / 6.0 + 1.0) / 6.0;
+        assert!((average_clustering(&graph) - expected_average).abs() < 1e-12);
+
+        // 2 triangles, triples: 3 + 1 + 6 + 1 + 1 + 0
+        assert!((transitivity(&graph) - 6.0 / 12.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cores() {
+        let graph = diamond_with_tail();
+        let cores = core_number(&graph);
+        assert_eq!(cores[&0], 2);
+        assert_eq!(cores[&1], 2);
+        assert_eq!(cores[&2], 2);
+        assert_eq!(cores[&3], 2);
+        assert_eq!(cores[&4], 1);
+        assert_eq!(cores[&5], 1);
+
+        let core: UnMapGraph<usize, (), ()> = k_core(&graph, 2);
+        assert_eq!(core.len(), (4, 5));
+        assert!(core.contains_node(3));
+        assert!(!core.contains_node(4));
+        assert!(core.contains_edge(3, 2));
+    }
+
+    #[test]
+    fn complete_graph_core() {
+        let mut edges = Vec::new();
+        for u in 0..5 {
+            for v in (u + 1)..5 {
+                edges.push((u, v, ()));
+            }
+        }
+        let graph = UnListGraph::from_ordinal(vec![(); 6], edges);
+        let cores = core_number(&graph);
+        assert!((0..5).all(|v| cores[&v] == 4));
+        assert_eq!(cores[&5], 0);
+        assert!((0..5).all(|v| triangles(&graph)[&v] == 6));
+        assert_eq!(transitivity(&graph), 1.0);
+    }
+}
@@ -0,0 +1,484 @@
+use crate::algo::errors::AlgoError;
+use crate::graph::traits::{Graph, GraphIter, UndirectedGraph};
+use crate::graph::types::NodeHashMap;
+use crate::iter::bfs::bfs;
+use crate::iter::traits::{Path, Tree};
+
+// The functions in this module expect the component of the given node to be a tree. Edges are
+// counted as length 1
+
+// Longest path in the tree containing start, found by two breadth first searches
+pub fn diameter<'a, G>(graph: &'a G, start: G::NId) -> Result<Path<'a, G>, AlgoError>
+where
+    G: UndirectedGraph,
+{
+    if !graph.contains_node(start) {
+        return Err(AlgoError::StartNodeNotFound(format!("{:?}", start)));
+    }
+
+    let (_, end) = bfs(graph, start).last().unwrap();
+    let mut search = bfs(graph, end.id());
+    let (_, other_end) = search.by_ref().last().unwrap();
+    Ok(search.path_to(other_end.id()).unwrap())
+}
+
+// Nodes minimizing the distance to the farthest node: the middle one or two nodes of a diameter
+pub fn center<G>(graph: &G, start: G::NId) -> Result<Vec<G::NId>, AlgoError>
+where
+    G: UndirectedGraph,
+{
+    let path: Vec<G::NId> = diameter(graph, start)?
+        .nodes()
+        .map(|node| node.id())
+        .collect();
+    let mid = path.len() / 2;
+    match path.len() % 2 {
+        1 => Ok(vec![path[mid]]),
+        _ => Ok(vec![path[mid - 1], path[mid]]),
+    }
+}
+
+// Nodes whose removal leaves no component with more than half of the nodes, one or two of them
+pub fn centroid<G>(graph: &G, start: G::NId) -> Result<Vec<G::NId>, AlgoError>
+where
+    G: UndirectedGraph,
+{
+    Ok(RootedTree::new(graph, start)?.centroid())
+}
+
+// Number of nodes in the subtree of every node when the tree is rooted at root
+pub fn subtree_sizes<G>(graph: &G, root: G::NId) -> Result<NodeHashMap<G, usize>, AlgoError>
+where
+    G: UndirectedGraph,
+{
+    Ok(RootedTree::new(graph, root)?.subtree_sizes())
+}
+
+// Evaluates a subtree dp for every choice of root in O(n) merges. The value of a subtree is
+// apply(merge of its children's values, subtree root), starting from identity, so merge must be
+// associative with identity as its neutral element. Returns the value of the whole tree when
+// rooted at each node
+pub fn reroot<G, T, M, A>(
+    graph: &G,
+    start: G::NId,
+    identity: T,
+    merge: M,
+    apply: A,
+) -> Result<NodeHashMap<G, T>, AlgoError>
+where
+    G: UndirectedGraph,
+    T: Clone,
+    M: Fn(&T, &T) -> T,
+    A: Fn(&T, G::NId) -> T,
+{
+    Ok(RootedTree::new(graph, start)?.reroot(identity, merge, apply))
+}
+
+// A rooted tree, with nodes indexed 0..n in breadth first order so every parent has a smaller index
+// than its children. It is either the component of an undirected graph that is a tree, or any Tree
+// such as the result of a traversal, in which case the graph may have other edges as well
+pub struct RootedTree<G>
+where
+    G: Graph,
+{
+    pub(crate) ids: Vec<G::NId>,
+    pub(crate) index: NodeHashMap<G, usize>,
+    pub(crate) parent: Vec<Option<(usize, G::EId)>>,
+    pub(crate) children: Vec<Vec<usize>>,
+}
+
+impl<G> RootedTree<G>
+where
+    G: Graph,
+{
+    pub fn new(graph: &G, root: G::NId) -> Result<RootedTree<G>, AlgoError>
+    where
+        G: UndirectedGraph,
+    {
+        if !graph.contains_node(root) {
+            return Err(AlgoError::StartNodeNotFound(format!("{:?}", root)));
+        }
+        Ok(Self::from_order(bfs(graph, root).map(|(edge, node)| {
+            let v = node.id();
+            (v, edge.map(|edge| (edge.other(v), edge.id())))
+        })))
+    }
+
+    // The nodes of tree below root, found by asking tree for the parent edge of every node of the
+    // graph. Nodes without one are the root or not part of the tree
+    pub fn from_tree<'a, T>(
+        graph: &'a G,
+        tree: &T,
+        root: G::NId,
+    ) -> Result<RootedTree<G>, AlgoError>
+    where
+        G: 'a + GraphIter,
+        T: Tree<'a, G>,
+    {
+        if !graph.contains_node(root) {
+            return Err(AlgoError::StartNodeNotFound(format!("{:?}", root)));
+        }
+        let mut below = NodeHashMap::<G, Vec<(G::NId, G::EId)>>::default();
+        for node in graph.nodes() {
+            let v = node.id();
+            if let Some(edge) = tree.parent_edge(v).filter(|_| v != root) {
+                below.entry(edge.other(v)).or_default().push((v, edge.id()));
+            }
+        }
+
+        let mut order = vec![(root, None)];
+        let mut next = 0;
+        while next < order.len() {
+            let u = order[next].0;
+            next += 1;
+            for (v, id) in below.remove(&u).unwrap_or_default() {
+                order.push((v, Some((u, id))));
+            }
+        }
+        Ok(Self::from_order(order))
+    }
+
+    // Nodes with their parent and the edge to it, every parent before its children
+    fn from_order(order: impl IntoIterator<Item = (G::NId, Option<(G::NId, G::EId)>)>) -> Self {
+        let mut ids = Vec::new();
+        let mut index = NodeHashMap::<G, usize>::default();
+        let mut parent = Vec::new();
+        let mut children = Vec::new();
+        for (id, up) in order {
+            let v = ids.len();
+            ids.push(id);
+            index.insert(id, v);
+            children.push(Vec::new());
+
+            let p = up.map(|(u, edge)| (index[&u], edge));
+            if let Some((u, _)) = p {
+                children[u].push(v);
+            }
+            parent.push(p);
+        }
+
+        RootedTree {
+            ids,
+            index,
+            parent,
+            children,
+        }
+    }
+
+    pub fn root(&self) -> G::NId {
+        self.ids[0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    // Longest path in the tree, from the deepest node below one node through it to the deepest node
+    // below another of its children
+    pub fn diameter<'a>(&self, graph: &'a G) -> Path<'a, G> {
+        let n = self.ids.len();
+        // deepest node below every node and how many edges down it is
+        let mut deepest: Vec<(usize, usize)> = (0..n).map(|v| (0, v)).collect();
+        let mut best = (0, 0, 0);
+        for v in (0..n).rev() {
+            let mut down = self.children[v]
+                .iter()
+                .map(|&c| (deepest[c].0 + 1, deepest[c].1))
+                .collect::<Vec<_>>();
+            down.sort_unstable_by(|a, b| b.cmp(a));
+            let first = down.first().copied().unwrap_or((0, v));
+            let second = down.get(1).copied().unwrap_or((0, v));
+            if first.0 + second.0 > best.0 {
+                best = (first.0 + second.0, first.1, second.1);
+            }
+            deepest[v] = first;
+        }
+
+        // up from one end to where the two sides meet, then down to the other end. Parents have
+        // smaller indices, so the larger of the two is never the meeting node
+        let (_, a, b) = best;
+        let (mut up, mut down) = (Vec::new(), Vec::new());
+        let (mut u, mut w) = (a, b);
+        while u != w {
+            if u > w {
+                up.push(u);
+                u = self.parent[u].unwrap().0;
+            } else {
+                down.push(w);
+                w = self.parent[w].unwrap().0;
+            }
+        }
+        up.push(u);
+        up.extend(down.into_iter().rev());
+
+        let mut steps = vec![(None, graph.node(self.ids[a]).unwrap())];
+        for (&x, &y) in up.iter().zip(&up[1..]) {
+            let child = if self.parent[x].is_some_and(|(p, _)| p == y) {
+                x
+            } else {
+                y
+            };
+            let edge = graph.edge(self.parent[child].unwrap().1).unwrap();
+            steps.push((Some(edge), graph.node(self.ids[y]).unwrap()));
+        }
+        Path::new(steps)
+    }
+
+    // Middle one or two nodes of a diameter
+    pub fn center(&self, graph: &G) -> Vec<G::NId> {
+        let path: Vec<G::NId> = self.diameter(graph).nodes().map(|node| node.id()).collect();
+        let mid = path.len() / 2;
+        match path.len() % 2 {
+            1 => vec![path[mid]],
+            _ => vec![path[mid - 1], path[mid]],
+        }
+    }
+
+    // Nodes whose removal leaves no part of the tree with more than half of the nodes
+    pub fn centroid(&self) -> Vec<G::NId> {
+        let sizes = self.sizes();
+        let n = self.ids.len();
+        (0..n)
+            .filter(|&v| {
+                let largest_child = self.children[v]
+                    .iter()
+                    .map(|&c| sizes[c])
+                    .max()
+                    .unwrap_or(0);
+                largest_child.max(n - sizes[v]) <= n / 2
+            })
+            .map(|v| self.ids[v])
+            .collect()
+    }
+
+    pub fn subtree_sizes(&self) -> NodeHashMap<G, usize> {
+        self.ids.iter().copied().zip(self.sizes()).collect()
+    }
+
+    // See reroot
+    pub fn reroot<T, M, A>(&self, identity: T, merge: M, apply: A) -> NodeHashMap<G, T>
+    where
+        T: Clone,
+        M: Fn(&T, &T) -> T,
+        A: Fn(&T, G::NId) -> T,
+    {
+        let n = self.ids.len();
+
+        // value of each subtree when rooted at the root, children come after parents
+        let mut down: Vec<T> = vec![identity.clone(); n];
+        for v in (0..n).rev() {
+            let children = self.children[v]
+                .iter()
+                .fold(identity.clone(), |acc, &c| merge(&acc, &down[c]));
+            down[v] = apply(&children, self.ids[v]);
+        }
+
+        // up[v] is the value of the parent's side of the tree, rooted at the parent and without v
+        let mut up: Vec<T> = vec![identity.clone(); n];
+        let mut result: Vec<T> = vec![identity.clone(); n];
+        for v in 0..n {
+            let children = &self.children[v];
+            let mut suffix = vec![identity.clone(); children.len() + 1];
+            for (i, &c) in children.iter().enumerate().rev() {
+                suffix[i] = merge(&down[c], &suffix[i + 1]);
+            }
+
+            let mut prefix = up[v].clone();
+            for (i, &c) in children.iter().enumerate() {
+                up[c] = apply(&merge(&prefix, &suffix[i + 1]), self.ids[v]);
+                prefix = merge(&prefix, &down[c]);
+            }
+            result[v] = apply(&prefix, self.ids[v]);
+        }
+
+        self.ids.iter().copied().zip(result).collect()
+    }
+
+    pub(crate) fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![1; self.ids.len()];
+        for v in (1..self.ids.len()).rev() {
+            if let Some((u, _)) = self.parent[v] {
+                sizes[u] += sizes[v];
+            }
+        }
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::errors::AlgoError;
+    use crate::algo::tree::{center, centroid, diameter, reroot, subtree_sizes, RootedTree};
+    use crate::graph::traits::{Graph, GraphIter, GraphMut, KeyedGraph, OrdinalGraph};
+    use crate::graph::types::{UnListGraph, UnMapGraph};
+    use crate::iter::bfs::bfs;
+    use crate::iter::traits::Tree;
+    use itertools::Itertools;
+    use std::collections::HashMap;
+
+    //       0
+    //     / | \
+    //    1  2  3
+    //   / \     \
+    //  4   5     6
+    //  |
+    //  7
+    fn tree() -> UnListGraph<(), ()> {
+        UnListGraph::builder()
+            .with_size(8)
+            .edge(0, 1, ())
+            .edge(0, 2, ())
+            .edge(0, 3, ())
+            .edge(1, 4, ())
+            .edge(1, 5, ())
+            .edge(3, 6, ())
+            .edge(4, 7, ())
+            .build()
+    }
+
+    #[test]
+    fn diameter_and_center() {
+        let graph = tree();
+        let path = diameter(&graph, 2).unwrap();
+        let mut nodes = path.nodes().map(|node| node.id()).collect_vec();
+        assert_eq!(path.edges().count(), 5);
+        if nodes[0] != 7 {
+            nodes.reverse();
+        }
+        assert_eq!(nodes, vec![7, 4, 1, 0, 3, 6]);
+
+        assert_eq!(
+            center(&graph, 5)
+                .unwrap()
+                .into_iter()
+                .sorted()
+                .collect_vec(),
+            vec![0, 1]
+        );
+
+        let single = UnListGraph::<(), ()>::from_ordinal(vec![()], vec![]);
+        assert_eq!(diameter(&single, 0).unwrap().edges().count(), 0);
+        assert_eq!(center(&single, 0).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn centroid_and_sizes() {
+        let graph = tree();
+        let sizes = subtree_sizes(&graph, 0).unwrap();
+        assert_eq!(sizes[&0], 8);
+        assert_eq!(sizes[&1], 4);
+        assert_eq!(sizes[&4], 2);
+        assert_eq!(sizes[&3], 2);
+        assert_eq!(sizes[&2], 1);
+
+        // removing 0 leaves components of sizes 4, 1, 2 and removing 1 leaves 4, 2, 1
+        assert_eq!(
+            centroid(&graph, 6)
+                .unwrap()
+                .into_iter()
+                .sorted()
+                .collect_vec(),
+            vec![0, 1]
+        );
+
+        let mut star = UnMapGraph::new();
+        star.put_node("hub", ());
+        for leaf in ["a", "b", "c"] {
+            star.put_node(leaf, ());
+            star.insert_edge("hub", leaf, ())
+                .expect("nodes should exist");
+        }
+        assert_eq!(centroid(&star, "a").unwrap(), vec!["hub"]);
+    }
+
+    #[test]
+    fn sum_of_distances_by_rerooting() {
+        let graph = tree();
+        // (nodes in subtree, sum of distances from the subtree root)
+        let sums = reroot(
+            &graph,
+            0,
+            (0, 0),
+            |a: &(usize, usize), b: &(usize, usize)| (a.0 + b.0, a.1 + b.1),
+            |acc, _| (acc.0 + 1, acc.1 + acc.0),
+        )
+        .unwrap();
+
+        for v in 0..8 {
+            let mut distance = HashMap::new();
+            for (edge, node) in bfs(&graph, v) {
+                let d = edge.map_or(0, |edge| distance[&edge.other(node.id())] + 1);
+                distance.insert(node.id(), d);
+            }
+            assert_eq!(sums[&v], (8, distance.values().sum()));
+        }
+        assert_eq!(sums[&0].1, 12);
+        assert_eq!(sums[&7].1, 22);
+    }
+
+    #[test]
+    fn bfs_tree_of_a_grid() {
+        // 3 x 4 grid, full of cycles, so only its bfs tree is a tree
+        let mut edges = Vec::new();
+        for v in 0..12 {
+            if v % 4 < 3 {
+                edges.push((v, v + 1, ()));
+            }
+            if v < 8 {
+                edges.push((v, v + 4, ()));
+            }
+        }
+        let graph = UnListGraph::<(), ()>::from_ordinal(vec![(); 12], edges);
+        let mut search = bfs(&graph, 5);
+        search.by_ref().for_each(drop);
+        let rooted = RootedTree::from_tree(&graph, &search, 5).unwrap();
+        assert_eq!((rooted.root(), rooted.len()), (5, 12));
+
+        // the same tree as a graph of its own
+        let tree_edges = graph
+            .nodes()
+            .filter_map(|node| search.parent_edge(node.id()))
+            .map(|edge| (edge.u(), edge.v(), ()))
+            .collect_vec();
+        let tree = UnListGraph::<(), ()>::from_ordinal(vec![(); 12], tree_edges);
+
+        assert_eq!(rooted.subtree_sizes(), subtree_sizes(&tree, 5).unwrap());
+        assert_eq!(
+            rooted.centroid().into_iter().sorted().collect_vec(),
+            centroid(&tree, 0)
+                .unwrap()
+                .into_iter()
+                .sorted()
+                .collect_vec()
+        );
+        let path = rooted.diameter(&graph);
+        assert_eq!(
+            path.edges().count(),
+            diameter(&tree, 0).unwrap().edges().count()
+        );
+        assert!(path
+            .edges()
+            .all(|edge| tree.between(edge.u(), edge.v()).is_some()));
+        assert_eq!(rooted.center(&graph).len(), center(&tree, 0).unwrap().len());
+
+        let count = |acc: &usize, _| acc + 1;
+        let sum = |a: &usize, b: &usize| a + b;
+        assert_eq!(
+            rooted.reroot(0, sum, count),
+            reroot(&tree, 3, 0, sum, count).unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_start() {
+        let graph = tree();
+        assert!(matches!(
+            diameter(&graph, 10),
+            Err(AlgoError::StartNodeNotFound(..))
+        ));
+    }
+}
@@ -0,0 +1,445 @@
+use crate::graph::traits::{OrdinalGraph, WithCapacity};
+
+use ahash::AHashSet;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Generators of random graphs with nodes 0..n. Unless stated otherwise they produce simple
+// undirected graphs: every pair of nodes u < v is joined at most once, by an edge u -> v, so in a
+// directed graph all edges point to the larger node. Node and edge data are left at their defaults.
+// The randomness comes from the rng that is passed in, so seeding it makes the graphs reproducible
+
+fn build<G>(n: usize, edges: Vec<(usize, usize)>) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    G::builder()
+        .nodes((0..n).map(|_| G::N::default()).collect())
+        .edges(
+            edges
+                .into_iter()
+                .map(|(u, v)| (u, v, G::E::default()))
+                .collect(),
+        )
+        .build()
+}
+
+// u < v
+fn ordered(u: usize, v: usize) -> (usize, usize) {
+    (u.min(v), u.max(v))
+}
+
+fn check_probability(p: f64) {
+    assert!(
+        (0.0..=1.0).contains(&p),
+        "probabilities have to be between 0 and 1, got {}",
+        p
+    );
+}
+
+// Pairs w < v of 0..n, each taken with probability p. Rather than flipping a coin for every pair,
+// the gaps between taken pairs are drawn from a geometric distribution (Batagelj and Brandes), so
+// this takes time linear in the number of pairs taken
+fn bernoulli_pairs(n: usize, p: f64, rng: &mut impl Rng) -> Vec<(usize, usize)> {
+    if p <= 0.0 || n < 2 {
+        return Vec::new();
+    }
+    if p >= 1.0 {
+        return (1..n).flat_map(|v| (0..v).map(move |w| (w, v))).collect();
+    }
+    let log_q = (1.0 - p).ln();
+    let mut pairs = Vec::new();
+    let (mut v, mut w) = (1usize, 0usize);
+    let mut skip = true;
+    while v < n {
+        let r: f64 = rng.gen();
+        let gap = ((1.0 - r).ln() / log_q).floor() as usize;
+        w = w.saturating_add(gap).saturating_add(usize::from(!skip));
+        skip = false;
+        while w >= v && v < n {
+            w -= v;
+            v += 1;
+        }
+        if v < n {
+            pairs.push((w, v));
+        }
+    }
+    pairs
+}
+
+// Erdős–Rényi G(n, p): every pair of nodes is joined with probability p
+pub fn gnp<G>(n: usize, p: f64, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    check_probability(p);
+    build(n, bernoulli_pairs(n, p, rng))
+}
+
+// Erdős–Rényi G(n, m): m edges between distinct pairs of nodes picked uniformly at random
+pub fn gnm<G>(n: usize, m: usize, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    let pairs = n * n.saturating_sub(1) / 2;
+    assert!(m <= pairs, "{} nodes have {} pairs, not {}", n, pairs, m);
+    // picking the pairs that are left out is quicker for dense graphs
+    let dense = 2 * m > pairs;
+    let picks = if dense { pairs - m } else { m };
+    let mut picked = AHashSet::with_capacity(picks);
+    while picked.len() < picks {
+        let (u, v) = (rng.gen_range(0..n), rng.gen_range(0..n));
+        if u != v {
+            picked.insert(ordered(u, v));
+        }
+    }
+    let edges = if dense {
+        (1..n)
+            .flat_map(|v| (0..v).map(move |u| (u, v)))
+            .filter(|pair| !picked.contains(pair))
+            .collect()
+    } else {
+        let mut edges: Vec<_> = picked.into_iter().collect();
+        edges.sort_unstable();
+        edges
+    };
+    build(n, edges)
+}
+
+// Barabási–Albert preferential attachment: starting from m nodes without edges, every new node is
+// joined to m distinct nodes picked with probability proportional to their degree, the first new
+// node to all of the m initial ones
+pub fn barabasi_albert<G>(n: usize, m: usize, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    assert!(
+        m >= 1 && m < n,
+        "every new node needs between 1 and {} edges, got {}",
+        n.saturating_sub(1),
+        m
+    );
+    let mut edges = Vec::with_capacity((n - m) * m);
+    // every node appears once per edge it has, so a uniform pick from here is proportional to degree
+    let mut ends: Vec<usize> = Vec::with_capacity(2 * (n - m) * m);
+    let mut targets: Vec<usize> = (0..m).collect();
+    for v in m..n {
+        for &u in &targets {
+            edges.push((u, v));
+            ends.push(u);
+            ends.push(v);
+        }
+        let mut next = AHashSet::with_capacity(m);
+        while next.len() < m {
+            next.insert(*ends.choose(rng).unwrap());
+        }
+        targets = next.into_iter().collect();
+        targets.sort_unstable();
+    }
+    build(n, edges)
+}
+
+// Watts–Strogatz small world: a ring where every node is joined to its k / 2 nearest neighbours on
+// either side, after which the far end of every edge is moved to a random node with probability p,
+// unless that would make a loop or join nodes that are already adjacent
+pub fn watts_strogatz<G>(n: usize, k: usize, p: f64, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    assert!(
+        k.is_multiple_of(2) && k < n,
+        "every node needs an even number of neighbours below {}, got {}",
+        n,
+        k
+    );
+    check_probability(p);
+    let mut ring: Vec<(usize, usize)> = (1..=k / 2)
+        .flat_map(|j| (0..n).map(move |u| (u, (u + j) % n)))
+        .collect();
+    let mut present: AHashSet<(usize, usize)> = ring.iter().map(|&(u, v)| ordered(u, v)).collect();
+    for (u, v) in ring.iter_mut() {
+        if !rng.gen_bool(p) {
+            continue;
+        }
+        let w = rng.gen_range(0..n);
+        if w == *u || present.contains(&ordered(*u, w)) {
+            continue;
+        }
+        present.remove(&ordered(*u, *v));
+        present.insert(ordered(*u, w));
+        *v = w;
+    }
+    build(n, ring.into_iter().map(|(u, v)| ordered(u, v)).collect())
+}
+
+// Random d-regular graph, by pairing up d copies of every node at random and keeping the pairs that
+// make neither loops nor repeated edges until none are left (Steger and Wormald), starting over when
+// the copies left can't be paired up anymore. That gets likely as d nears n, so a graph with d above
+// (n - 1) / 2 is made as the complement of a random (n - 1 - d)-regular one
+pub fn random_regular<G>(n: usize, d: usize, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    assert!(
+        d < n.max(1) && (n * d).is_multiple_of(2),
+        "no {}-regular graph with {} nodes",
+        d,
+        n
+    );
+    let mut edges: Vec<_> = if 2 * d > n.saturating_sub(1) {
+        let missing = regular_pairs(n, n - 1 - d, rng);
+        (1..n)
+            .flat_map(|v| (0..v).map(move |u| (u, v)))
+            .filter(|pair| !missing.contains(pair))
+            .collect()
+    } else {
+        regular_pairs(n, d, rng).into_iter().collect()
+    };
+    edges.sort_unstable();
+    build(n, edges)
+}
+
+fn regular_pairs(n: usize, d: usize, rng: &mut impl Rng) -> AHashSet<(usize, usize)> {
+    'attempt: loop {
+        let mut edges = AHashSet::with_capacity(n * d / 2);
+        let mut stubs: Vec<usize> = (0..n).flat_map(|u| std::iter::repeat_n(u, d)).collect();
+        while !stubs.is_empty() {
+            stubs.shuffle(rng);
+            let mut left = Vec::new();
+            for pair in stubs.chunks(2) {
+                let (u, v) = (pair[0], pair[1]);
+                if u != v && edges.insert(ordered(u, v)) {
+                    continue;
+                }
+                left.extend([u, v]);
+            }
+            let stuck = !left.iter().enumerate().any(|(i, &u)| {
+                left[i + 1..]
+                    .iter()
+                    .any(|&v| u != v && !edges.contains(&ordered(u, v)))
+            });
+            if !left.is_empty() && stuck {
+                continue 'attempt;
+            }
+            stubs = left;
+        }
+        return edges;
+    }
+}
+
+// Tree picked uniformly among the n^(n - 2) labelled trees with n nodes, decoded from a random
+// Prüfer sequence
+pub fn random_tree<G>(n: usize, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    if n < 2 {
+        return build(n, Vec::new());
+    }
+    let sequence: Vec<usize> = (0..n - 2).map(|_| rng.gen_range(0..n)).collect();
+    // a node stays inner while it appears in the rest of the sequence
+    let mut degree = vec![1; n];
+    for &u in &sequence {
+        degree[u] += 1;
+    }
+    let mut leaves: BinaryHeap<Reverse<usize>> =
+        (0..n).filter(|&u| degree[u] == 1).map(Reverse).collect();
+    let mut edges = Vec::with_capacity(n - 1);
+    for &u in &sequence {
+        let Reverse(leaf) = leaves.pop().unwrap();
+        edges.push(ordered(leaf, u));
+        degree[u] -= 1;
+        if degree[u] == 1 {
+            leaves.push(Reverse(u));
+        }
+    }
+    let Reverse(u) = leaves.pop().unwrap();
+    let Reverse(v) = leaves.pop().unwrap();
+    edges.push(ordered(u, v));
+    build(n, edges)
+}
+
+// Random directed acyclic graph: the nodes are put in a random order and every pair is joined with
+// probability p by an edge from the node that comes first to the one that comes later. Unlike the
+// other generators the edges don't always point to the larger node
+pub fn random_dag<G>(n: usize, p: f64, rng: &mut impl Rng) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    check_probability(p);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(rng);
+    let edges = bernoulli_pairs(n, p, rng)
+        .into_iter()
+        .map(|(w, v)| (order[w], order[v]))
+        .collect();
+    build(n, edges)
+}
+
+// Stochastic block model: sizes[i] nodes in block i, numbered block after block, where two nodes
+// of blocks i <= j are joined with probability probabilities[i][j]. Only the upper triangle of the
+// matrix is read
+pub fn stochastic_block_model<G>(
+    sizes: &[usize],
+    probabilities: &[Vec<f64>],
+    rng: &mut impl Rng,
+) -> G
+where
+    G: OrdinalGraph<NId = usize> + WithCapacity,
+    G::N: Default,
+    G::E: Default,
+{
+    assert!(
+        probabilities.len() >= sizes.len() && probabilities.iter().all(|p| p.len() >= sizes.len()),
+        "{} blocks need a {0} x {0} matrix of probabilities",
+        sizes.len()
+    );
+    for i in 0..sizes.len() {
+        probabilities[i][i..sizes.len()]
+            .iter()
+            .for_each(|&p| check_probability(p));
+    }
+    let block: Vec<usize> = sizes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &size)| std::iter::repeat_n(i, size))
+        .collect();
+    let mut edges = Vec::new();
+    for v in 1..block.len() {
+        for u in 0..v {
+            if rng.gen_bool(probabilities[block[u]][block[v]]) {
+                edges.push((u, v));
+            }
+        }
+    }
+    build(block.len(), edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::components::connected_components;
+    use crate::algo::topological_sort::topological_sort;
+    use crate::generators::random::{
+        barabasi_albert, gnm, gnp, random_dag, random_regular, random_tree, stochastic_block_model,
+        watts_strogatz,
+    };
+    use crate::graph::traits::{Graph, GraphIter};
+    use crate::graph::types::{DiListGraph, UnListGraph};
+    use ahash::AHashSet;
+    use itertools::Itertools;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    type Un = UnListGraph<(), ()>;
+
+    fn components(graph: &Un) -> usize {
+        connected_components(graph).values().unique().count()
+    }
+
+    fn simple(graph: &Un) -> bool {
+        let mut pairs = AHashSet::new();
+        graph.edges().all(|edge| {
+            edge.u() != edge.v() && pairs.insert((edge.u().min(edge.v()), edge.u().max(edge.v())))
+        })
+    }
+
+    #[test]
+    fn erdos_renyi() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let graph: Un = gnp(200, 0.05, &mut rng);
+        assert_eq!(graph.n(), 200);
+        assert!(simple(&graph));
+        // 995 edges expected, with a standard deviation of 31
+        assert!((850..1150).contains(&graph.m()));
+        assert_eq!(gnp::<Un>(10, 1.0, &mut rng).m(), 45);
+        assert_eq!(gnp::<Un>(10, 0.0, &mut rng).m(), 0);
+
+        for m in [0, 30, 40, 45] {
+            let graph: Un = gnm(10, m, &mut rng);
+            assert_eq!(graph.m(), m);
+            assert!(simple(&graph));
+        }
+
+        // the same seed gives the same graph
+        let edges = |graph: Un| {
+            graph
+                .edges()
+                .map(|edge| (edge.u(), edge.v()))
+                .collect::<Vec<_>>()
+        };
+        let a: Un = gnp(50, 0.1, &mut StdRng::seed_from_u64(3));
+        let b: Un = gnp(50, 0.1, &mut StdRng::seed_from_u64(3));
+        assert_eq!(edges(a), edges(b));
+    }
+
+    #[test]
+    fn degree_structured() {
+        let mut rng = StdRng::seed_from_u64(11);
+
+        let graph: Un = barabasi_albert(100, 3, &mut rng);
+        assert_eq!(graph.m(), 97 * 3);
+        assert!(simple(&graph));
+        assert!((3..100).all(|u| graph.degree(u) >= 3));
+        assert_eq!(components(&graph), 1);
+
+        let graph: Un = watts_strogatz(60, 4, 0.2, &mut rng);
+        assert_eq!(graph.m(), 120);
+        assert!(simple(&graph));
+
+        for (n, d) in [(20, 3), (31, 4), (6, 5), (200, 150), (201, 100), (1, 0)] {
+            let graph: Un = random_regular(n, d, &mut rng);
+            assert!(simple(&graph));
+            assert!((0..n).all(|u| graph.degree(u) == d));
+        }
+    }
+
+    #[test]
+    fn trees_and_dags() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for n in [1, 2, 3, 50] {
+            let tree: Un = random_tree(n, &mut rng);
+            assert_eq!(tree.m(), n - 1);
+            assert_eq!(components(&tree), 1);
+        }
+
+        let dag: DiListGraph<(), ()> = random_dag(40, 0.3, &mut rng);
+        assert!(dag.m() > 0);
+        assert!(topological_sort(&dag).is_ok());
+    }
+
+    #[test]
+    fn block_model() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let graph: Un =
+            stochastic_block_model(&[20, 30], &[vec![1.0, 0.0], vec![0.0, 1.0]], &mut rng);
+        // two cliques without edges between them
+        assert_eq!(graph.m(), 190 + 435);
+        assert_eq!(components(&graph), 2);
+
+        let graph: Un =
+            stochastic_block_model(&[20, 20], &[vec![0.5, 0.05], vec![0.05, 0.5]], &mut rng);
+        let across = graph
+            .edges()
+            .filter(|edge| (edge.u() < 20) != (edge.v() < 20))
+            .count();
+        assert!(across < graph.m() / 4);
+    }
+}
@@ -6,7 +6,85 @@ pub trait Weighted<W> {
     fn set_weight(&mut self, val: W);
 }
 
+// Weight arithmetic that reports overflow instead of wrapping or panicking, for algorithms whose
+// intermediate values grow beyond the weight of any path, like sums scaled by a number of edges
+pub trait CheckedWeight: Sized {
+    fn checked_plus(&self, other: &Self) -> Option<Self>;
+    // self added to itself k times
+    fn checked_times(&self, k: usize) -> Option<Self>;
+}
+
 pub trait Capacity<C> {
     fn capacity(&self) -> C;
     fn set_capacity(&mut self, val: C);
 }
+
+// Plain numbers are their own weight and capacity, so graphs with numeric edge data work with every
+// algorithm that reads weights or capacities through these traits
+macro_rules! impl_numeric {
+    ($($t:ty),*) => {
+        $(
+            impl Weighted<$t> for $t {
+                fn weight(&self) -> $t {
+                    *self
+                }
+
+                fn set_weight(&mut self, val: $t) {
+                    *self = val;
+                }
+            }
+
+            impl Capacity<$t> for $t {
+                fn capacity(&self) -> $t {
+                    *self
+                }
+
+                fn set_capacity(&mut self, val: $t) {
+                    *self = val;
+                }
+            }
+        )*
+    };
+}
+
+impl_numeric!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+macro_rules! impl_checked_integer {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedWeight for $t {
+                fn checked_plus(&self, other: &$t) -> Option<$t> {
+                    self.checked_add(*other)
+                }
+
+                fn checked_times(&self, k: usize) -> Option<$t> {
+                    if *self == 0 {
+                        return Some(0);
+                    }
+                    <$t>::try_from(k).ok().and_then(|k| self.checked_mul(k))
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// floats don't overflow, they become infinite
+macro_rules! impl_checked_float {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedWeight for $t {
+                fn checked_plus(&self, other: &$t) -> Option<$t> {
+                    Some(self + other)
+                }
+
+                fn checked_times(&self, k: usize) -> Option<$t> {
+                    Some(self * k as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_float!(f32, f64);
@@ -8,6 +8,9 @@ pub enum GraphError {
     #[error("edge {0:?} not found in graph")]
     EdgeNotFound(String),
 
+    #[error("unknown map cell {0:?} at line {1}, column {2}")]
+    UnknownMapCell(char, usize, usize),
+
     #[error("flow error: {0}")]
     FlowError(#[from] FlowError),
 
@@ -22,4 +25,7 @@ pub enum FlowError {
 
     #[error("insufficient remaining capacity in edge {0:?} to increase flow: {1:?}")]
     InsufficientCapacity(String, String),
+
+    #[error("lower bound {0:?} exceeds capacity {1:?}")]
+    InvalidBounds(String, String),
 }
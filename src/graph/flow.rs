@@ -53,6 +53,27 @@ pub trait FlowGraph: GraphMut<E = Flow<<Self as FlowGraph>::FlowVal>> {
         capacity: Self::FlowVal,
     ) -> Result<(Self::EId, Self::EId), GraphError>;
 
+    // adds flow edge whose flow must stay within [lower, capacity] and its back edge, returns ids
+    // for both. By default the pair is inserted like any flow edge and the lower bound is set after
+    fn insert_bounded_flow_edge(
+        &mut self,
+        u: Self::NId,
+        v: Self::NId,
+        lower: Self::FlowVal,
+        capacity: Self::FlowVal,
+    ) -> Result<(Self::EId, Self::EId), GraphError> {
+        if lower > capacity {
+            return Err(FlowError::InvalidBounds(
+                format!("{:?}", lower),
+                format!("{:?}", capacity),
+            )
+            .into());
+        }
+        let (forward_id, back_id) = self.insert_flow_edge(u, v, capacity)?;
+        *self.edge_mut(forward_id).unwrap().data() = Flow::new_bounded(lower, capacity);
+        Ok((forward_id, back_id))
+    }
+
     // removes front and back edge, returns value for front edge and id + value for back edge
     fn remove_flow_edge(
         &mut self,
@@ -67,6 +88,10 @@ pub trait FlowGraph: GraphMut<E = Flow<<Self as FlowGraph>::FlowVal>> {
 pub trait FlowValue:
     Add<Output = Self> + Sub<Output = Self> + Neg<Output = Self> + Ord + Copy + Default + Debug
 {
+    // Floating point values override this to ignore amounts within rounding error of zero
+    fn is_positive(&self) -> bool {
+        *self > Self::default()
+    }
 }
 impl FlowValue for i8 {}
 impl FlowValue for i16 {}
@@ -74,24 +99,40 @@ impl FlowValue for i32 {}
 impl FlowValue for i64 {}
 impl FlowValue for i128 {}
 impl FlowValue for isize {}
-//impl FlowValue for f32 {}
-//impl FlowValue for f64 {}
+// floats are supported through utils::float::Float
 
+// Lower bounds only constrain forward edges, and only once the flow meets them: max flow algorithms
+// ignore them, while circulations give the back edge of an edge with lower bound l capacity -l
+// after routing at least l through it, so residual paths never push the forward flow below l
 #[derive(Default, Copy, Clone, Debug)]
 pub struct Flow<V: FlowValue> {
     flow: V,
     capacity: V,
+    lower: V,
 }
 
 impl<V: FlowValue> Flow<V> {
     pub fn new(flow: V, capacity: V) -> Self {
-        Self { flow, capacity }
+        Self {
+            flow,
+            capacity,
+            lower: V::default(),
+        }
     }
 
     pub fn new_forward(capacity: V) -> Self {
         Self {
             flow: V::default(),
             capacity,
+            lower: V::default(),
+        }
+    }
+
+    pub fn new_bounded(lower: V, capacity: V) -> Self {
+        Self {
+            flow: V::default(),
+            capacity,
+            lower,
         }
     }
 
@@ -99,9 +140,16 @@ impl<V: FlowValue> Flow<V> {
         Self {
             flow: V::default(),
             capacity: V::default(),
+            lower: V::default(),
         }
     }
 
+    // On the back edge of an edge that carries at least its lower bound, keeps residual paths from
+    // taking it below that
+    pub fn hold_lower_bound(&mut self, lower: V) {
+        self.capacity = -lower;
+    }
+
     pub fn flow(&self) -> V {
         self.flow
     }
@@ -110,24 +158,30 @@ impl<V: FlowValue> Flow<V> {
         self.capacity
     }
 
+    pub fn lower(&self) -> V {
+        self.lower
+    }
+
     pub fn residual(&self) -> V {
         self.capacity - self.flow
     }
 
     pub fn has_residual(&self) -> bool {
-        self.residual() > V::default()
+        self.residual().is_positive()
     }
 
     pub fn increase_flow(&mut self, delta: V) -> Result<(), String> {
-        if self.flow + delta > self.capacity {
+        if (self.flow + delta - self.capacity).is_positive() {
             return Err(format!("{:?}", &self));
         }
         self.flow = self.flow + delta;
         Ok(())
     }
 
+    // A back edge that held a lower bound lets go of it, as zero flow falls short of the bound
     pub fn reset_flow(&mut self) {
         self.flow = V::default();
+        self.capacity = self.capacity.max(V::default());
     }
 }
 
@@ -7,6 +7,7 @@ use std::{
 use itertools::{Itertools, Product};
 
 use super::state::StateGraph;
+use crate::utils::float::F64;
 
 #[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Direction {
@@ -35,17 +36,152 @@ impl Direction {
     }
 }
 
+// Moves that lead from a state on a grid to its neighbours
+pub trait Neighbourhood<State> {
+    // States one move away from state, before any bounds are applied
+    fn neighbours(&self, state: State) -> Vec<State>;
+
+    // Neighbours of a state on the bounds, where a move across the border of toroidal bounds
+    // comes back in on the other side. Moves that wrap back onto state or onto the same neighbour
+    // as another move are left out
+    fn wrapped_neighbours(&self, state: State, bounds: &GridBounds) -> Vec<State>
+    where
+        State: Eq + Hash + Copy + From<(isize, isize)> + Into<(isize, isize)>,
+    {
+        self.neighbours(state)
+            .into_iter()
+            .map(|adj| bounds.wrap(adj))
+            .filter(|&adj| adj != state)
+            .unique()
+            .collect()
+    }
+}
+
+// Neighbours up, down, left and right
+#[derive(Clone, Copy, Debug)]
+pub struct FourConnected;
+
+// Neighbours up, down, left and right and along the diagonals
+#[derive(Clone, Copy, Debug)]
+pub struct EightConnected;
+
+// Neighbours of a hexagon in axial coordinates (q, r)
+#[derive(Clone, Copy, Debug)]
+pub struct HexAxial;
+
+impl<State> Neighbourhood<State> for FourConnected
+where
+    State: Copy + Add<Direction, Output = State>,
+{
+    fn neighbours(&self, state: State) -> Vec<State> {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+        .into_iter()
+        .map(|d| state + d)
+        .collect()
+    }
+}
+
+impl<State> Neighbourhood<State> for EightConnected
+where
+    State: Copy + Add<Direction, Output = State>,
+{
+    fn neighbours(&self, state: State) -> Vec<State> {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+            Direction::UpLeft,
+            Direction::UpRight,
+            Direction::DownLeft,
+            Direction::DownRight,
+        ]
+        .into_iter()
+        .map(|d| state + d)
+        .collect()
+    }
+}
+
+impl<State> Neighbourhood<State> for HexAxial
+where
+    State: From<(isize, isize)> + Into<(isize, isize)>,
+{
+    fn neighbours(&self, state: State) -> Vec<State> {
+        let (q, r) = state.into();
+        Grid::HEX_AXIAL
+            .iter()
+            .map(|&(dq, dr)| State::from((q + dq, r + dr)))
+            .collect()
+    }
+}
+
+// Any list of offsets, such as Grid::KNIGHT or Grid::voxel_offsets
+impl<State, D> Neighbourhood<State> for Vec<D>
+where
+    State: Copy + Add<D, Output = State>,
+    D: Copy,
+{
+    fn neighbours(&self, state: State) -> Vec<State> {
+        self.iter().map(|&offset| state + offset).collect()
+    }
+}
+
 pub struct Grid {}
 
 impl Grid {
-    pub fn four_connected<State, N, E, NV, EV, NF, EF>(
+    // Moves of a chess knight
+    pub const KNIGHT: [(isize, isize); 8] = [
+        (-2, -1),
+        (-2, 1),
+        (-1, -2),
+        (-1, 2),
+        (1, -2),
+        (1, 2),
+        (2, -1),
+        (2, 1),
+    ];
+
+    // Neighbours of a hexagon in axial coordinates (q, r)
+    pub const HEX_AXIAL: [(isize, isize); 6] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)];
+
+    // Neighbours of a voxel that share a face (6), at least an edge (18) or at least a corner (26)
+    pub fn voxel_offsets(connectivity: usize) -> Vec<(isize, isize, isize)> {
+        let max_changed = match connectivity {
+            6 => 1,
+            18 => 2,
+            26 => 3,
+            _ => panic!(
+                "voxel connectivity has to be 6, 18 or 26, got {}",
+                connectivity
+            ),
+        };
+        (-1..=1)
+            .cartesian_product(-1..=1)
+            .cartesian_product(-1..=1)
+            .map(|((x, y), z)| (x, y, z))
+            .filter(|&(x, y, z)| {
+                let changed = [x, y, z].iter().filter(|&&d| d != 0).count();
+                changed > 0 && changed <= max_changed
+            })
+            .collect()
+    }
+
+    // Grid where the neighbours of a state are given by the neighbourhood
+    pub fn with_neighbourhood<State, Nb, N, E, NV, EV, NF, EF>(
+        neighbourhood: Nb,
         node_val: NV,
         edge_val: EV,
         node_filter: NF,
         edge_filter: EF,
-    ) -> StateGraph<State, N, E, NV, EV, impl Fn(State) -> Vec<State>, impl Fn(State) -> bool>
+    ) -> StateGraph<State, N, E, NV, EV, impl Fn(State) -> Vec<State>, NF>
     where
-        State: Eq + Hash + Copy + Debug + Add<Direction, Output = State>,
+        State: Eq + Hash + Copy + Debug,
+        Nb: Neighbourhood<State>,
         NV: Fn(State) -> N,
         EV: Fn(State, State) -> E,
         NF: Fn(State) -> bool,
@@ -55,62 +191,99 @@ impl Grid {
             node_val,
             edge_val,
             move |state: State| {
-                let mut neighbors = Vec::new();
-                for direction in [
-                    Direction::Up,
-                    Direction::Down,
-                    Direction::Left,
-                    Direction::Right,
-                ] {
-                    let adj = state + direction;
-                    if (edge_filter)(state, adj) {
-                        neighbors.push(adj);
-                    }
-                }
-                neighbors
+                neighbourhood
+                    .neighbours(state)
+                    .into_iter()
+                    .filter(|&adj| (edge_filter)(state, adj))
+                    .collect()
             },
             node_filter,
         )
     }
 
-    pub fn eight_connected<State, N, E, NV, EV, NF, EF>(
+    // Grid where the neighbours of a state are the state moved by each of the offsets
+    pub fn with_offsets<State, D, N, E, NV, EV, NF, EF>(
+        offsets: Vec<D>,
+        node_val: NV,
+        edge_val: EV,
+        node_filter: NF,
+        edge_filter: EF,
+    ) -> StateGraph<State, N, E, NV, EV, impl Fn(State) -> Vec<State>, NF>
+    where
+        State: Eq + Hash + Copy + Debug + Add<D, Output = State>,
+        D: Copy,
+        NV: Fn(State) -> N,
+        EV: Fn(State, State) -> E,
+        NF: Fn(State) -> bool,
+        EF: Fn(State, State) -> bool,
+    {
+        Grid::with_neighbourhood(offsets, node_val, edge_val, node_filter, edge_filter)
+    }
+
+    // Grid of the states within the bounds, where moves across the border of toroidal bounds
+    // come back in on the other side
+    pub fn bounded<State, Nb, N, E, NV, EV, NF, EF>(
+        neighbourhood: Nb,
+        bounds: GridBounds,
         node_val: NV,
         edge_val: EV,
         node_filter: NF,
         edge_filter: EF,
     ) -> StateGraph<State, N, E, NV, EV, impl Fn(State) -> Vec<State>, impl Fn(State) -> bool>
     where
-        State: Eq + Hash + Copy + Debug + Add<Direction, Output = State>,
+        State: Eq + Hash + Copy + Debug + From<(isize, isize)> + Into<(isize, isize)>,
+        Nb: Neighbourhood<State>,
         NV: Fn(State) -> N,
         EV: Fn(State, State) -> E,
         NF: Fn(State) -> bool,
         EF: Fn(State, State) -> bool,
     {
+        let filter_bounds = bounds.clone();
         StateGraph::new(
             node_val,
             edge_val,
             move |state: State| {
-                let mut neighbors = Vec::new();
-                for direction in [
-                    Direction::Up,
-                    Direction::Down,
-                    Direction::Left,
-                    Direction::Right,
-                    Direction::UpLeft,
-                    Direction::UpRight,
-                    Direction::DownLeft,
-                    Direction::DownRight,
-                ] {
-                    let adj = state + direction;
-                    if (edge_filter)(state, adj) {
-                        neighbors.push(adj);
-                    }
-                }
-                neighbors
+                neighbourhood
+                    .wrapped_neighbours(state, &bounds)
+                    .into_iter()
+                    .filter(|&adj| (edge_filter)(state, adj))
+                    .collect()
             },
-            node_filter,
+            move |state| filter_bounds.check(state) && (node_filter)(state),
         )
     }
+
+    pub fn four_connected<State, N, E, NV, EV, NF, EF>(
+        node_val: NV,
+        edge_val: EV,
+        node_filter: NF,
+        edge_filter: EF,
+    ) -> StateGraph<State, N, E, NV, EV, impl Fn(State) -> Vec<State>, NF>
+    where
+        State: Eq + Hash + Copy + Debug + Add<Direction, Output = State>,
+        NV: Fn(State) -> N,
+        EV: Fn(State, State) -> E,
+        NF: Fn(State) -> bool,
+        EF: Fn(State, State) -> bool,
+    {
+        Grid::with_neighbourhood(FourConnected, node_val, edge_val, node_filter, edge_filter)
+    }
+
+    pub fn eight_connected<State, N, E, NV, EV, NF, EF>(
+        node_val: NV,
+        edge_val: EV,
+        node_filter: NF,
+        edge_filter: EF,
+    ) -> StateGraph<State, N, E, NV, EV, impl Fn(State) -> Vec<State>, NF>
+    where
+        State: Eq + Hash + Copy + Debug + Add<Direction, Output = State>,
+        NV: Fn(State) -> N,
+        EV: Fn(State, State) -> E,
+        NF: Fn(State) -> bool,
+        EF: Fn(State, State) -> bool,
+    {
+        Grid::with_neighbourhood(EightConnected, node_val, edge_val, node_filter, edge_filter)
+    }
 }
 
 #[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
@@ -134,6 +307,37 @@ impl Add<Direction> for Position {
     }
 }
 
+impl Add<(isize, isize)> for Position {
+    type Output = Position;
+
+    fn add(self, (di, dj): (isize, isize)) -> Position {
+        Position(self.0 + di, self.1 + dj)
+    }
+}
+
+impl Position {
+    pub fn offset(self, di: isize, dj: isize) -> Position {
+        Position(self.0 + di, self.1 + dj)
+    }
+
+    // Steps between the two positions when only moving up, down, left and right
+    pub fn manhattan(self, other: Position) -> usize {
+        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
+    }
+
+    // Steps between the two positions when diagonal moves cost the same as straight ones
+    pub fn chebyshev(self, other: Position) -> usize {
+        self.0.abs_diff(other.0).max(self.1.abs_diff(other.1))
+    }
+
+    // Distance between the two positions when straight moves cost 1 and diagonal moves sqrt(2)
+    pub fn octile(self, other: Position) -> F64 {
+        let (di, dj) = (self.0.abs_diff(other.0), self.1.abs_diff(other.1));
+        let (short, long) = (di.min(dj) as f64, di.max(dj) as f64);
+        F64::new(long + (std::f64::consts::SQRT_2 - 1.0) * short)
+    }
+}
+
 impl From<(isize, isize)> for Position {
     fn from(value: (isize, isize)) -> Self {
         Position(value.0, value.1)
@@ -146,9 +350,34 @@ impl From<Position> for (isize, isize) {
     }
 }
 
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Voxel(isize, isize, isize);
+
+impl Add<(isize, isize, isize)> for Voxel {
+    type Output = Voxel;
+
+    fn add(self, (dx, dy, dz): (isize, isize, isize)) -> Voxel {
+        Voxel(self.0 + dx, self.1 + dy, self.2 + dz)
+    }
+}
+
+impl From<(isize, isize, isize)> for Voxel {
+    fn from(value: (isize, isize, isize)) -> Self {
+        Voxel(value.0, value.1, value.2)
+    }
+}
+
+impl From<Voxel> for (isize, isize, isize) {
+    fn from(value: Voxel) -> Self {
+        (value.0, value.1, value.2)
+    }
+}
+
+#[derive(Clone)]
 pub struct GridBounds {
     i: Option<Range<isize>>,
     j: Option<Range<isize>>,
+    wrap: bool,
 }
 
 impl GridBounds {
@@ -156,20 +385,56 @@ impl GridBounds {
         GridBounds {
             i: Some(i),
             j: Some(j),
+            wrap: false,
+        }
+    }
+
+    // Bounds of a torus, where leaving on one side means coming back in on the opposite one
+    pub fn toroidal(i: Range<isize>, j: Range<isize>) -> GridBounds {
+        GridBounds {
+            i: Some(i),
+            j: Some(j),
+            wrap: true,
         }
     }
 
     pub fn unbounded() -> GridBounds {
-        GridBounds { i: None, j: None }
+        GridBounds {
+            i: None,
+            j: None,
+            wrap: false,
+        }
     }
 
-    pub fn check(&self, p: Position) -> bool {
+    // State p ends up at after wrapping around toroidal bounds, p itself otherwise
+    pub fn wrap<P>(&self, p: P) -> P
+    where
+        P: From<(isize, isize)> + Into<(isize, isize)>,
+    {
+        if !self.wrap {
+            return p;
+        }
+        let (i, j) = p.into();
+        let wrap = |x: isize, range: &Option<Range<isize>>| match range {
+            Some(range) if !range.is_empty() => {
+                range.start + (x - range.start).rem_euclid(range.end - range.start)
+            }
+            _ => x,
+        };
+        P::from((wrap(i, &self.i), wrap(j, &self.j)))
+    }
+
+    pub fn check<P>(&self, p: P) -> bool
+    where
+        P: Into<(isize, isize)>,
+    {
+        let (i, j) = p.into();
         let check_i = match &self.i {
-            Some(range) => range.contains(&p.0),
+            Some(range) => range.contains(&i),
             _ => true,
         };
         let check_j = match &self.j {
-            Some(range) => range.contains(&p.1),
+            Some(range) => range.contains(&j),
             _ => true,
         };
         check_i && check_j
@@ -197,9 +462,12 @@ impl GridBounds {
 
 #[cfg(test)]
 mod tests {
+    use crate::graph::traits::Graph;
     use crate::iter::bfs::bfs;
+    use crate::iter::traits::Traversal;
 
-    use super::{Grid, GridBounds, Position};
+    use super::{Direction, Grid, GridBounds, HexAxial, Position, Voxel};
+    use crate::graph::grid::{EightConnected, FourConnected, Neighbourhood};
 
     #[test]
     fn four_connected_bfs() {
@@ -221,4 +489,94 @@ mod tests {
             ]
         );
     }
+
+    fn bfs_distance<G>(graph: &G, from: G::NId, to: G::NId) -> Option<usize>
+    where
+        G: Graph,
+    {
+        bfs(graph, from)
+            .find_path_to(to)
+            .map(|path| path.edges().count())
+    }
+
+    #[test]
+    fn knight_moves() {
+        let bounds = GridBounds::new(0..8, 0..8);
+        let g = Grid::bounded(
+            Grid::KNIGHT.to_vec(),
+            bounds,
+            |_| (),
+            |_, _| 1,
+            |_| true,
+            |_, _| true,
+        );
+        assert_eq!(g.adj(Position(0, 0)).unwrap().count(), 2);
+        assert_eq!(g.adj(Position(3, 3)).unwrap().count(), 8);
+        assert_eq!(bfs_distance(&g, Position(0, 0), Position(7, 7)), Some(6));
+        assert_eq!(bfs_distance(&g, Position(0, 0), Position(1, 1)), Some(4));
+    }
+
+    #[test]
+    fn hex_and_voxel_neighborhoods() {
+        let hex = Grid::with_neighbourhood(HexAxial, |_| (), |_, _| 1, |_| true, |_, _| true);
+        // hex distance is (|dq| + |dr| + |dq + dr|) / 2
+        assert_eq!(bfs_distance(&hex, Position(0, 0), Position(2, -3)), Some(3));
+        assert_eq!(bfs_distance(&hex, Position(0, 0), Position(2, 1)), Some(3));
+
+        for (connectivity, diagonal) in [(6, 3), (18, 2), (26, 1)] {
+            let offsets = Grid::voxel_offsets(connectivity);
+            assert_eq!(offsets.len(), connectivity);
+            let voxels = Grid::with_offsets(offsets, |_| (), |_, _| 1, |_| true, |_, _| true);
+            assert_eq!(
+                bfs_distance(&voxels, Voxel(0, 0, 0), Voxel(1, 1, 1)),
+                Some(diagonal)
+            );
+        }
+    }
+
+    #[test]
+    fn toroidal_bounds() {
+        let bounds = GridBounds::toroidal(0..5, 0..4);
+        assert_eq!(bounds.wrap(Position(-1, 4)), Position(4, 0));
+        let g = Grid::bounded(
+            FourConnected,
+            bounds.clone(),
+            |_| (),
+            |_, _| 1,
+            |_| true,
+            |_, _| true,
+        );
+        assert_eq!(bfs_distance(&g, Position(0, 0), Position(4, 3)), Some(2));
+        assert_eq!(bfs(&g, Position(0, 0)).count(), 20);
+
+        // any state with coordinates can wrap, such as plain (q, r) pairs of a hex torus
+        let hex = Grid::bounded(HexAxial, bounds, |_| (), |_, _| 1, |_| true, |_, _| true);
+        assert_eq!(bfs_distance(&hex, (0, 0), (4, 3)), Some(2));
+        assert_eq!(bfs(&hex, (0, 0)).count(), 20);
+    }
+
+    #[test]
+    fn neighbourhoods_agree_with_directions() {
+        let p = Position(2, 3);
+        let four: Vec<Position> = FourConnected.neighbours(p);
+        let eight: Vec<Position> = EightConnected.neighbours(p);
+        assert_eq!(
+            four,
+            vec![
+                p + Direction::Up,
+                p + Direction::Down,
+                p + Direction::Left,
+                p + Direction::Right
+            ]
+        );
+        assert_eq!(eight.len(), 8);
+        assert!(four.iter().all(|q| eight.contains(q)));
+
+        // on a torus one cell wide, moving left or right leads back to the start
+        let narrow = GridBounds::toroidal(0..5, 0..1);
+        assert_eq!(
+            FourConnected.wrapped_neighbours(Position(0, 0), &narrow),
+            vec![Position(4, 0), Position(1, 0)]
+        );
+    }
 }
@@ -0,0 +1,238 @@
+use ahash::AHashMap;
+
+use crate::graph::errors::GraphError;
+use crate::graph::grid::Position;
+use crate::graph::traits::{Graph, GraphMut, KeyedGraph, WithCapacity};
+use crate::graph::types::DiMapGraph;
+use crate::iter::traits::Path;
+
+// What a character of an ASCII map stands for. Floor and special cells are walkable and cost the
+// given amount to step onto, special cells are additionally reported by their character
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Cell {
+    Wall,
+    Floor(u32),
+    Special(u32),
+}
+
+pub struct MapParser {
+    cells: AHashMap<char, Cell>,
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Default for MapParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MapParser {
+    // '#' and ' ' are walls, '.' is floor, '0' to '9' are floor costing that digit and 'S' and 'E'
+    // mark start and goal. Cells are connected to their four orthogonal neighbours
+    pub fn new() -> Self {
+        let mut cells = AHashMap::from([
+            ('#', Cell::Wall),
+            (' ', Cell::Wall),
+            ('.', Cell::Floor(1)),
+            ('S', Cell::Special(1)),
+            ('E', Cell::Special(1)),
+        ]);
+        for (cost, digit) in ('0'..='9').enumerate() {
+            cells.insert(digit, Cell::Floor(cost as u32));
+        }
+        MapParser {
+            cells,
+            offsets: vec![(-1, 0), (1, 0), (0, -1), (0, 1)],
+        }
+    }
+
+    pub fn cell(mut self, c: char, cell: Cell) -> Self {
+        self.cells.insert(c, cell);
+        self
+    }
+
+    // Moves between cells, as (row, column) offsets
+    pub fn offsets(mut self, offsets: Vec<(isize, isize)>) -> Self {
+        self.offsets = offsets;
+        self
+    }
+
+    // Row i, column j of the text becomes Position (i, j). Every walkable cell gets an edge to each
+    // walkable neighbour weighing the cost of that neighbour. Lines shorter than others are padded
+    // with walls
+    pub fn parse(&self, text: &str) -> Result<GridMap, GraphError> {
+        let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        let mut graph = DiMapGraph::with_capacity(
+            rows.iter().map(|row| row.len()).sum(),
+            self.offsets.len() * rows.iter().map(|row| row.len()).sum::<usize>(),
+        );
+        let mut specials: AHashMap<char, Vec<Position>> = AHashMap::new();
+        let mut cells = Vec::new();
+        let mut costs = AHashMap::new();
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                let p = Position::from((i as isize, j as isize));
+                let cost = match self.cells.get(&c) {
+                    Some(Cell::Wall) => continue,
+                    Some(Cell::Floor(cost)) => *cost,
+                    Some(Cell::Special(cost)) => {
+                        specials.entry(c).or_default().push(p);
+                        *cost
+                    }
+                    None => return Err(GraphError::UnknownMapCell(c, i + 1, j + 1)),
+                };
+                graph.put_node(p, c);
+                cells.push(p);
+                costs.insert(p, cost);
+            }
+        }
+
+        for u in cells {
+            for &(di, dj) in &self.offsets {
+                let v = u.offset(di, dj);
+                if let Some(&cost) = costs.get(&v) {
+                    graph.insert_edge(u, v, cost)?;
+                }
+            }
+        }
+
+        Ok(GridMap {
+            graph,
+            specials,
+            rows,
+        })
+    }
+}
+
+pub struct GridMap {
+    graph: DiMapGraph<Position, char, u32>,
+    specials: AHashMap<char, Vec<Position>>,
+    rows: Vec<Vec<char>>,
+}
+
+impl GridMap {
+    // Parses a map with the default characters of MapParser::new
+    pub fn parse(text: &str) -> Result<GridMap, GraphError> {
+        MapParser::new().parse(text)
+    }
+
+    pub fn graph(&self) -> &DiMapGraph<Position, char, u32> {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> DiMapGraph<Position, char, u32> {
+        self.graph
+    }
+
+    // First cell marked with c, in reading order
+    pub fn special(&self, c: char) -> Option<Position> {
+        self.specials(c).first().copied()
+    }
+
+    pub fn specials(&self, c: char) -> &[Position] {
+        self.specials.get(&c).map_or(&[], |cells| cells.as_slice())
+    }
+
+    // The map text with the nodes of the path drawn as mark. Special cells keep their character
+    pub fn render<'a, G>(&self, path: &Path<'a, G>, mark: char) -> String
+    where
+        G: 'a + Graph<NId = Position>,
+    {
+        self.overlay(path.nodes().map(|node| node.id()), mark)
+    }
+
+    // The map text with the given cells drawn as mark. Special cells and cells outside the map are
+    // left alone
+    pub fn overlay(&self, cells: impl IntoIterator<Item = Position>, mark: char) -> String {
+        let mut rows = self.rows.clone();
+        for p in cells {
+            let (i, j): (isize, isize) = p.into();
+            let Some(c) = usize::try_from(i)
+                .ok()
+                .zip(usize::try_from(j).ok())
+                .and_then(|(i, j)| rows.get_mut(i)?.get_mut(j))
+            else {
+                continue;
+            };
+            if !self.specials.contains_key(c) {
+                *c = mark;
+            }
+        }
+        rows.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::graph::errors::GraphError;
+    use crate::graph::grid::Position;
+    use crate::graph::grid_map::{Cell, GridMap, MapParser};
+    use crate::graph::traits::{Graph, GraphIter};
+    use crate::iter::traits::{Tree, WeightedPathTree};
+
+    #[test]
+    fn parse_and_render_shortest_path() {
+        let text = "\
+#######
+#S..9.#
+#.#.#.#
+#...#E#
+#######";
+        let map = GridMap::parse(text).unwrap();
+        let (start, goal) = (map.special('S').unwrap(), map.special('E').unwrap());
+        assert_eq!(start, Position::from((1, 1)));
+        assert_eq!(goal, Position::from((3, 5)));
+        assert_eq!(map.graph().n(), 12);
+        assert!(!map.graph().contains_node(Position::from((2, 2))));
+        assert_eq!(
+            map.graph().node(Position::from((1, 4))).unwrap().data(),
+            &'9'
+        );
+
+        // the only way round costs 9 to cross the expensive cell
+        let tree = WeightedPathTree::from(dijkstra(map.graph(), start).unwrap());
+        let path = tree.path_to(goal).unwrap();
+        assert_eq!(tree.weight(goal), Some(&14));
+        assert_eq!(
+            map.render(&path, '*'),
+            "\
+#######
+#S****#
+#.#.#*#
+#...#E#
+#######"
+        );
+    }
+
+    #[test]
+    fn custom_cells() {
+        let parser = MapParser::new()
+            .cell('~', Cell::Floor(5))
+            .cell('K', Cell::Special(1))
+            .cell('D', Cell::Special(1))
+            .offsets(vec![(0, 1), (1, 0)]);
+        let map = parser.parse("S~K\n.D\n..E").unwrap();
+        assert_eq!(map.specials('K'), &[Position::from((0, 2))]);
+        assert_eq!(map.specials('X'), &[]);
+        // only moves right and down, and the short middle line has a wall at its end
+        assert!(!map.graph().contains_node(Position::from((1, 2))));
+        assert_eq!(
+            map.graph().adj_ids(Position::from((0, 2))).unwrap().count(),
+            0
+        );
+        let entering_water = map
+            .graph()
+            .between(Position::from((0, 0)), Position::from((0, 1)))
+            .unwrap();
+        assert_eq!(entering_water.data(), &5);
+
+        assert!(matches!(
+            GridMap::parse("S.x"),
+            Err(GraphError::UnknownMapCell('x', 1, 3))
+        ));
+    }
+}
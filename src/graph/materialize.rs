@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+
+use crate::graph::keyed::Keyed;
+use crate::graph::traits::{Graph, GraphMut, KeyedGraph, WithCapacity};
+use crate::graph::types::{DiFlatGraph, NodeHashMap};
+
+// Upper bound on the nodes room is made for up front, since max_nodes is only a limit and can be
+// far more than the search ever reaches
+const INITIAL_CAPACITY: usize = 1024;
+
+pub type Materialized<G> = Keyed<DiFlatGraph<<G as Graph>::N, <G as Graph>::E>, <G as Graph>::NId>;
+
+// Copies the part of the graph reachable from the starts into a concrete graph with the same node
+// ids, so a lazily defined graph like a StateGraph can be handed to algorithms that need GraphIter.
+// Nodes are taken in breadth first order until max_nodes of them have been found, and nodes
+// max_depth steps away from every start are kept without following their edges. Every edge
+// followed becomes a directed edge, so an undirected graph comes out with both directions
+pub fn materialize<G>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::NId>,
+    max_nodes: Option<usize>,
+    max_depth: Option<usize>,
+) -> Materialized<G>
+where
+    G: Graph,
+    G::N: Clone,
+    G::E: Clone,
+{
+    let mut result =
+        Materialized::<G>::with_capacity(max_nodes.unwrap_or(0).min(INITIAL_CAPACITY), 0);
+    let mut depth = NodeHashMap::<G, usize>::default();
+    let mut queue = VecDeque::new();
+    let room = |depth: &NodeHashMap<G, usize>| max_nodes.is_none_or(|k| depth.len() < k);
+
+    for start in starts {
+        let Some(node) = graph.node(start) else {
+            continue;
+        };
+        if depth.contains_key(&start) || !room(&depth) {
+            continue;
+        }
+        depth.insert(start, 0);
+        result.put_node(start, node.data().clone());
+        queue.push_back(start);
+    }
+
+    while let Some(u) = queue.pop_front() {
+        let d = depth[&u];
+        if max_depth.is_some_and(|k| d >= k) {
+            continue;
+        }
+        for (edge, node) in graph.adj(u).unwrap() {
+            let v = node.id();
+            if !depth.contains_key(&v) {
+                if !room(&depth) {
+                    continue;
+                }
+                depth.insert(v, d + 1);
+                result.put_node(v, node.data().clone());
+                queue.push_back(v);
+            }
+            result
+                .insert_edge(u, v, edge.data().clone())
+                .expect("both ends should have been added");
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::shortest_paths::bellman_ford::bellman_ford;
+    use crate::algo::topological_sort::topological_sort;
+    use crate::graph::materialize::materialize;
+    use crate::graph::state::StateGraph;
+    use crate::graph::traits::{Graph, GraphIter, OrdinalGraph};
+    use crate::graph::types::UnListGraph;
+    use crate::iter::traits::Tree;
+
+    #[test]
+    fn materialize_state_graph() {
+        // from n go to n + 1 or 2n, costing n mod 3 - 1
+        let graph = StateGraph::new(
+            |n: i64| n * n,
+            |u: i64, _| u % 3 - 1,
+            |n: i64| vec![n + 1, 2 * n],
+            |n| n < 40,
+        );
+        let concrete = materialize(&graph, [1], None, None);
+        assert_eq!(concrete.n(), 39);
+        // 38 steps up and 19 doublings, where 1 + 1 and 2 * 1 are two separate edges
+        assert_eq!(concrete.m(), 38 + 19);
+        assert_eq!(concrete.node(6).unwrap().data(), &36);
+
+        let order: Vec<_> = topological_sort(&concrete)
+            .unwrap()
+            .iter()
+            .map(|node| node.id())
+            .collect();
+        assert_eq!(order.len(), 39);
+        assert_eq!(order[0], 1);
+
+        let (tree, cycle) = bellman_ford(&concrete, 1).unwrap();
+        assert!(cycle.is_none());
+        assert_eq!(tree.weight(1), Some(&0));
+        assert!(tree.path_to(39).is_some());
+    }
+
+    #[test]
+    fn materialize_limits() {
+        let graph = StateGraph::new(|_| (), |_, _| (), |n: u32| vec![n + 1, n + 2], |_| true);
+        let shallow = materialize(&graph, [0, 100], None, Some(2));
+        // 0..=4 and 100..=104, without the edges out of the nodes two steps away
+        assert_eq!(shallow.n(), 10);
+        assert!(shallow.contains_edge(1, 3));
+        assert!(!shallow.contains_edge(3, 4));
+
+        let small = materialize(&graph, [0], Some(5), None);
+        assert_eq!(small.n(), 5);
+        assert!((0..5).all(|n| small.contains_node(n)));
+
+        // a huge limit doesn't get allocated up front
+        let limited = materialize(&graph, [0], Some(usize::MAX), Some(3));
+        assert_eq!(limited.n(), 7);
+
+        // an undirected edge shows up both ways
+        let path = UnListGraph::<(), ()>::from_ordinal(vec![(); 3], vec![(0, 1, ()), (1, 2, ())]);
+        let both_ways = materialize(&path, [0], None, None);
+        assert_eq!(both_ways.m(), 4);
+        assert!(both_ways.contains_edge(2, 1));
+    }
+}
@@ -24,3 +24,5 @@ pub mod builder;
 
 pub mod state;
 pub mod grid;
+pub mod grid_map;
+pub mod materialize;
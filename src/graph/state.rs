@@ -1,16 +1,77 @@
-use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+use std::{cell::RefCell, fmt::Debug, hash::Hash, marker::PhantomData};
 
-use ahash::AHasher;
+use ahash::{AHashMap, AHasher};
 use itertools::Itertools;
 
-use super::{edge::Edge, node::Node, traits::Graph};
+use super::{
+    edge::Edge,
+    node::Node,
+    traits::{Graph, MultiGraph},
+};
+
+// Moves out of a state, as a closure from a state to its successors or remembered in a cache
+pub trait Transitions<State, S> {
+    fn successors(&self, u: State) -> Vec<S>;
+}
+
+impl<State, S, T> Transitions<State, S> for T
+where
+    T: Fn(State) -> Vec<S>,
+{
+    fn successors(&self, u: State) -> Vec<S> {
+        (self)(u)
+    }
+}
+
+// Transitions that remember the successors of every expanded state, for when they are expensive
+// to compute and the graph gets searched more than once. The transition runs with the cache
+// unborrowed, so it may look at the graph it belongs to
+pub struct CachedTransitions<State, S, T> {
+    transition: T,
+    cache: RefCell<AHashMap<State, Vec<S>>>,
+}
+
+impl<State, S, T> CachedTransitions<State, S, T> {
+    pub fn new(transition: T) -> Self {
+        CachedTransitions {
+            transition,
+            cache: RefCell::default(),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+impl<State, S, T> Transitions<State, S> for CachedTransitions<State, S, T>
+where
+    State: Eq + Hash + Copy,
+    S: Clone,
+    T: Fn(State) -> Vec<S>,
+{
+    fn successors(&self, u: State) -> Vec<S> {
+        if let Some(successors) = self.cache.borrow().get(&u) {
+            return successors.clone();
+        }
+        let successors = (self.transition)(u);
+        self.cache.borrow_mut().insert(u, successors.clone());
+        successors
+    }
+}
+
+pub type CachedStateGraph<State, N, E, NV, EV, T, F> =
+    StateGraph<State, N, E, NV, EV, CachedTransitions<State, State, T>, F>;
+
+pub type CachedWeightedStateGraph<State, N, E, NV, T, F> =
+    WeightedStateGraph<State, N, E, NV, CachedTransitions<State, (State, E), T>, F>;
 
 pub struct StateGraph<State, N, E, NV, EV, T, F>
 where
     State: Eq + Hash + Copy + Debug,
     NV: Fn(State) -> N,
     EV: Fn(State, State) -> E,
-    T: Fn(State) -> Vec<State>,
+    T: Transitions<State, State>,
     F: Fn(State) -> bool,
 {
     node_val: NV,
@@ -37,15 +98,39 @@ where
             _phantom: PhantomData::default(),
         }
     }
+
+    // Remembers the transitions out of every expanded state, see CachedTransitions
+    pub fn cached(self) -> CachedStateGraph<State, N, E, NV, EV, T, F> {
+        StateGraph {
+            node_val: self.node_val,
+            edge_val: self.edge_val,
+            transition: CachedTransitions::new(self.transition),
+            filter: self.filter,
+            _phantom: PhantomData,
+        }
+    }
 }
 
-impl<State, N, E, NV, EV, T, F> Graph for StateGraph<State, N, E, NV, EV, T, F>
+impl<State, N, E, NV, EV, T, F> CachedStateGraph<State, N, E, NV, EV, T, F>
 where
     State: Eq + Hash + Copy + Debug,
     NV: Fn(State) -> N,
     EV: Fn(State, State) -> E,
     T: Fn(State) -> Vec<State>,
     F: Fn(State) -> bool,
+{
+    pub fn clear_cache(&self) {
+        self.transition.clear();
+    }
+}
+
+impl<State, N, E, NV, EV, T, F> Graph for StateGraph<State, N, E, NV, EV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    NV: Fn(State) -> N,
+    EV: Fn(State, State) -> E,
+    T: Transitions<State, State>,
+    F: Fn(State) -> bool,
 {
     type NId = State;
     type N = N;
@@ -71,17 +156,14 @@ where
         Some(Node::from_value(id, val))
     }
     fn degree(&self, u: Self::NId) -> usize {
-        (self.transition)(u).len()
+        self.transition.successors(u).len()
     }
 
     fn contains_edge(&self, u: Self::NId, v: Self::NId) -> bool {
         if !self.contains_node(u) || !self.contains_node(v) {
             return false;
         }
-        (self.transition)(u)
-            .into_iter()
-            .find(|&state| state == v)
-            .is_some()
+        self.transition.successors(u).contains(&v)
     }
 
     fn edge(&self, id: Self::EId) -> Option<Edge<Self::NId, Self::EId, Self::E>> {
@@ -100,7 +182,8 @@ where
             return None;
         }
         Some(
-            (self.transition)(u)
+            self.transition
+                .successors(u)
                 .into_iter()
                 .filter(|v| (self.filter)(*v))
                 .filter_map(|v| Some((self.edge((u, v))?, self.node(v)?)))
@@ -114,7 +197,8 @@ where
             return None;
         }
         Some(
-            (self.transition)(u)
+            self.transition
+                .successors(u)
                 .into_iter()
                 .filter(|v| (self.filter)(*v))
                 .map(|v| ((u, v), v))
@@ -123,3 +207,256 @@ where
         )
     }
 }
+
+// State graph whose transitions come with the value of the move, so several moves between the
+// same two states can have different values. Edges are identified by the state they leave and
+// their index among its transitions
+pub struct WeightedStateGraph<State, N, E, NV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    E: Clone,
+    NV: Fn(State) -> N,
+    T: Transitions<State, (State, E)>,
+    F: Fn(State) -> bool,
+{
+    node_val: NV,
+    transition: T,
+    filter: F,
+    _phantom: PhantomData<(State, N, E)>,
+}
+
+impl<State, N, E, NV, T, F> WeightedStateGraph<State, N, E, NV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    E: Clone,
+    NV: Fn(State) -> N,
+    T: Fn(State) -> Vec<(State, E)>,
+    F: Fn(State) -> bool,
+{
+    pub fn new(node_val: NV, transition: T, filter: F) -> Self {
+        Self {
+            node_val,
+            transition,
+            filter,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Remembers the transitions out of every expanded state, see CachedTransitions
+    pub fn cached(self) -> CachedWeightedStateGraph<State, N, E, NV, T, F> {
+        WeightedStateGraph {
+            node_val: self.node_val,
+            transition: CachedTransitions::new(self.transition),
+            filter: self.filter,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<State, N, E, NV, T, F> CachedWeightedStateGraph<State, N, E, NV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    E: Clone,
+    NV: Fn(State) -> N,
+    T: Fn(State) -> Vec<(State, E)>,
+    F: Fn(State) -> bool,
+{
+    pub fn clear_cache(&self) {
+        self.transition.clear();
+    }
+}
+
+impl<State, N, E, NV, T, F> WeightedStateGraph<State, N, E, NV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    E: Clone,
+    NV: Fn(State) -> N,
+    T: Transitions<State, (State, E)>,
+    F: Fn(State) -> bool,
+{
+    // Edges out of u that lead to a state within the graph
+    fn out_edges(&self, u: State) -> Vec<Edge<'_, State, (State, usize), E>> {
+        if !self.contains_node(u) {
+            return Vec::new();
+        }
+        self.transition
+            .successors(u)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (v, _))| (self.filter)(*v))
+            .map(|(i, (v, val))| Edge::from_value((u, i), u, v, val))
+            .collect()
+    }
+}
+
+impl<State, N, E, NV, T, F> Graph for WeightedStateGraph<State, N, E, NV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    E: Clone,
+    NV: Fn(State) -> N,
+    T: Transitions<State, (State, E)>,
+    F: Fn(State) -> bool,
+{
+    type NId = State;
+    type N = N;
+    type NodeHasher = AHasher;
+    type EId = (State, usize);
+    type E = E;
+    type AdjIterator<'a> = std::vec::IntoIter<
+        (
+            Edge<'a, Self::NId, Self::EId, Self::E>,
+            Node<'a, Self::NId, Self::N>,
+        ),
+    > where Self: 'a;
+    type AdjIdsIterator<'a> = std::vec::IntoIter<(Self::EId, Self::NId)> where Self: 'a;
+
+    fn contains_node(&self, id: Self::NId) -> bool {
+        (self.filter)(id)
+    }
+    fn node(&self, id: Self::NId) -> Option<Node<'_, Self::NId, Self::N>> {
+        if !self.contains_node(id) {
+            return None;
+        }
+        Some(Node::from_value(id, (self.node_val)(id)))
+    }
+    fn degree(&self, u: Self::NId) -> usize {
+        self.out_edges(u).len()
+    }
+
+    fn contains_edge(&self, u: Self::NId, v: Self::NId) -> bool {
+        self.between(u, v).is_some()
+    }
+
+    fn edge(&self, id: Self::EId) -> Option<Edge<'_, Self::NId, Self::EId, Self::E>> {
+        self.out_edges(id.0)
+            .into_iter()
+            .find(|edge| edge.id() == id)
+    }
+
+    // The first of the moves from u to v
+    fn between(
+        &self,
+        u: Self::NId,
+        v: Self::NId,
+    ) -> Option<Edge<'_, Self::NId, Self::EId, Self::E>> {
+        self.out_edges(u).into_iter().find(|edge| edge.v() == v)
+    }
+
+    fn adj<'a>(&'a self, u: Self::NId) -> Option<Self::AdjIterator<'a>> {
+        if !self.contains_node(u) {
+            return None;
+        }
+        Some(
+            self.out_edges(u)
+                .into_iter()
+                .filter_map(|edge| {
+                    let node = self.node(edge.v())?;
+                    Some((edge, node))
+                })
+                .collect_vec()
+                .into_iter(),
+        )
+    }
+
+    fn adj_ids<'a>(&'a self, u: Self::NId) -> Option<Self::AdjIdsIterator<'a>> {
+        if !self.contains_node(u) {
+            return None;
+        }
+        Some(
+            self.out_edges(u)
+                .into_iter()
+                .map(|edge| (edge.id(), edge.v()))
+                .collect_vec()
+                .into_iter(),
+        )
+    }
+}
+
+impl<State, N, E, NV, T, F> MultiGraph for WeightedStateGraph<State, N, E, NV, T, F>
+where
+    State: Eq + Hash + Copy + Debug,
+    E: Clone,
+    NV: Fn(State) -> N,
+    T: Transitions<State, (State, E)>,
+    F: Fn(State) -> bool,
+{
+    type MultiEdgeIterator<'a> = std::vec::IntoIter<Edge<'a, State, (State, usize), E>> where Self: 'a;
+
+    fn between_multi<'a>(
+        &'a self,
+        u: Self::NId,
+        v: Self::NId,
+    ) -> Option<Self::MultiEdgeIterator<'a>> {
+        if !self.contains_node(u) {
+            return None;
+        }
+        Some(
+            self.out_edges(u)
+                .into_iter()
+                .filter(|edge| edge.v() == v)
+                .collect_vec()
+                .into_iter(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::graph::state::{StateGraph, WeightedStateGraph};
+    use crate::graph::traits::{Graph, MultiGraph};
+    use crate::iter::traits::{Tree, WeightedPathTree};
+
+    #[test]
+    fn weighted_transitions() {
+        // from n either walk to n + 1 for 3 or take the bus to n + 1 for 1 or to n + 5 for 4
+        let graph = WeightedStateGraph::new(
+            |_| (),
+            |n: u32| vec![(n + 1, 3), (n + 1, 1), (n + 5, 4)],
+            |n| n <= 10,
+        );
+        assert_eq!(graph.between_multi(0, 1).unwrap().count(), 2);
+        assert_eq!(graph.between(0, 1).unwrap().data(), &3);
+        assert_eq!(graph.edge((0, 1)).unwrap().data(), &1);
+        assert_eq!(graph.degree(8), 2);
+
+        let tree = WeightedPathTree::from(dijkstra(&graph, 0).unwrap());
+        assert_eq!(tree.weight(10), Some(&8));
+        assert_eq!(tree.weight(3), Some(&3));
+        let path = tree.path_to(5).unwrap();
+        assert_eq!(
+            path.edges().map(|edge| edge.id()).collect::<Vec<_>>(),
+            vec![(0, 2)]
+        );
+    }
+
+    #[test]
+    fn cached_transitions() {
+        let calls = Cell::new(0);
+        let transition = |n: u32| {
+            calls.set(calls.get() + 1);
+            vec![n + 1, n + 2]
+        };
+        let graph = StateGraph::new(|_| (), |_, _| 1, transition, |n| n < 20).cached();
+        for _ in 0..3 {
+            let tree = WeightedPathTree::from(dijkstra(&graph, 0).unwrap());
+            assert_eq!(tree.weight(19), Some(&10));
+        }
+        assert_eq!(calls.get(), 20);
+
+        graph.clear_cache();
+        assert!(graph.contains_edge(4, 6));
+        assert_eq!(calls.get(), 21);
+
+        // only the cached graph holds a RefCell, so a plain one can still be shared across threads
+        fn is_sync<T: Sync>(_: &T) {}
+        is_sync(&StateGraph::new(
+            |_| (),
+            |_, _| 1,
+            |n: u32| vec![n + 1],
+            |n| n < 20,
+        ));
+    }
+}
@@ -2,3 +2,4 @@ pub mod graph;
 pub mod iter;
 pub mod algo;
 pub mod utils;
+pub mod generators;
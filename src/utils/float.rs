@@ -0,0 +1,246 @@
+use crate::graph::edge_traits::{Capacity, CheckedWeight, Weighted};
+use crate::graph::flow::FlowValue;
+
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Float with a total order, so it can be used as edge weight or flow value wherever Ord is needed.
+// NaN equals itself and compares greater than every other value, and 0.0 equals -0.0. As flow value
+// it treats amounts up to EPSILON as zero, so rounding errors don't leave phantom residual capacity
+#[derive(Default, Copy, Clone)]
+pub struct Float<F>(pub F);
+
+pub type F32 = Float<f32>;
+pub type F64 = Float<f64>;
+
+macro_rules! impl_float {
+    ($f:ty, $epsilon:expr) => {
+        impl Float<$f> {
+            pub const EPSILON: $f = $epsilon;
+
+            pub fn new(value: $f) -> Self {
+                Float(value)
+            }
+
+            pub fn value(self) -> $f {
+                self.0
+            }
+
+            // Same value up to EPSILON
+            pub fn approx_eq(self, other: Self) -> bool {
+                (self.0 - other.0).abs() <= Self::EPSILON
+            }
+        }
+
+        impl PartialEq for Float<$f> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for Float<$f> {}
+
+        impl PartialOrd for Float<$f> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Float<$f> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0
+                    .partial_cmp(&other.0)
+                    .unwrap_or_else(|| self.0.is_nan().cmp(&other.0.is_nan()))
+            }
+        }
+
+        impl Hash for Float<$f> {
+            // equal values must hash the same, so -0.0 and every NaN hash like 0.0 and NaN
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                let canonical = match self.0 {
+                    x if x.is_nan() => <$f>::NAN,
+                    x if x == 0.0 => 0.0,
+                    x => x,
+                };
+                canonical.to_bits().hash(state);
+            }
+        }
+
+        impl From<$f> for Float<$f> {
+            fn from(value: $f) -> Self {
+                Float(value)
+            }
+        }
+
+        impl Add for Float<$f> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Float(self.0 + other.0)
+            }
+        }
+
+        impl Sub for Float<$f> {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Float(self.0 - other.0)
+            }
+        }
+
+        impl Mul for Float<$f> {
+            type Output = Self;
+
+            fn mul(self, other: Self) -> Self {
+                Float(self.0 * other.0)
+            }
+        }
+
+        impl Div for Float<$f> {
+            type Output = Self;
+
+            fn div(self, other: Self) -> Self {
+                Float(self.0 / other.0)
+            }
+        }
+
+        impl Neg for Float<$f> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Float(-self.0)
+            }
+        }
+
+        impl Sum for Float<$f> {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Float(iter.map(|x| x.0).sum())
+            }
+        }
+
+        impl Debug for Float<$f> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl Display for Float<$f> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                Display::fmt(&self.0, f)
+            }
+        }
+
+        impl FlowValue for Float<$f> {
+            fn is_positive(&self) -> bool {
+                self.0 > Self::EPSILON
+            }
+        }
+
+        impl Weighted<Float<$f>> for Float<$f> {
+            fn weight(&self) -> Self {
+                *self
+            }
+
+            fn set_weight(&mut self, val: Self) {
+                *self = val;
+            }
+        }
+
+        impl CheckedWeight for Float<$f> {
+            fn checked_plus(&self, other: &Self) -> Option<Self> {
+                Some(*self + *other)
+            }
+
+            fn checked_times(&self, k: usize) -> Option<Self> {
+                Some(Float(self.0 * k as $f))
+            }
+        }
+
+        impl Capacity<Float<$f>> for Float<$f> {
+            fn capacity(&self) -> Self {
+                *self
+            }
+
+            fn set_capacity(&mut self, val: Self) {
+                *self = val;
+            }
+        }
+    };
+}
+
+impl_float!(f32, 1e-5);
+impl_float!(f64, 1e-9);
+
+#[cfg(test)]
+mod tests {
+    use crate::algo::flow::dinic::dinic;
+    use crate::algo::shortest_paths::dijkstra::dijkstra;
+    use crate::graph::flow::FlowGraph;
+    use crate::graph::traits::OrdinalGraph;
+    use crate::graph::types::{DiListGraph, FlowFlatGraph};
+    use crate::iter::traits::Traversal;
+    use crate::utils::float::{Float, F64};
+
+    #[test]
+    fn total_order() {
+        let mut values = [
+            F64::new(2.5),
+            F64::new(f64::NAN),
+            F64::new(-1.0),
+            F64::new(0.0),
+        ];
+        values.sort();
+        assert_eq!(values[0], Float(-1.0));
+        assert_eq!(values[1], Float(-0.0));
+        assert_eq!(values[2], Float(2.5));
+        assert!(values[3].value().is_nan());
+        assert_eq!(Float(f64::NAN), Float(f64::NAN));
+        assert!(F64::new(0.1 + 0.2).approx_eq(Float(0.3)));
+    }
+
+    #[test]
+    fn dijkstra_with_real_weights() {
+        let graph = DiListGraph::from_ordinal(
+            vec![(); 4],
+            vec![
+                (0, 1, F64::new(0.5)),
+                (1, 3, F64::new(0.25)),
+                (0, 2, F64::new(0.1)),
+                (2, 3, F64::new(0.7)),
+            ],
+        );
+        let mut search = dijkstra(&graph, 0).unwrap();
+        let path = search.find_path_to(3).unwrap();
+        assert_eq!(
+            path.nodes().map(|node| node.id()).collect::<Vec<_>>(),
+            vec![0, 1, 3]
+        );
+    }
+
+    #[test]
+    fn dinic_with_rounding() {
+        // 0.1 + 0.2 units squeezed through an edge of capacity 0.3 leave no residual worth pushing
+        let mut graph = FlowFlatGraph::<(), F64>::new();
+        for _ in 0..4 {
+            graph.insert_node(());
+        }
+        graph.insert_flow_edge(0, 1, Float(0.1)).unwrap();
+        graph.insert_flow_edge(0, 2, Float(0.2)).unwrap();
+        graph.insert_flow_edge(1, 3, Float(1.0)).unwrap();
+        graph.insert_flow_edge(2, 3, Float(1.0)).unwrap();
+        let flow = dinic(&mut graph, 0, 3).unwrap();
+        assert!(flow.approx_eq(Float(0.3)));
+
+        let mut graph = FlowFlatGraph::<(), F64>::new();
+        for _ in 0..3 {
+            graph.insert_node(());
+        }
+        graph.insert_flow_edge(0, 1, Float(0.1 + 0.2)).unwrap();
+        graph.insert_flow_edge(1, 2, Float(0.3)).unwrap();
+        let flow = dinic(&mut graph, 0, 2).unwrap();
+        assert!(flow.approx_eq(Float(0.3)));
+    }
+}
@@ -1 +1,2 @@
 pub mod disjoint_sets;
+pub mod float;